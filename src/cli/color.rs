@@ -0,0 +1,100 @@
+//! Choice of the escape codes that colored output can use
+//!
+//! Colors are disabled when the output is not a terminal or when
+//! `NO_COLOR` is set, unless they are explicitly requested.
+//! Otherwise `COLORTERM` and `TERM` determine how many colors are available,
+//! 24-bit colors are approximated on terminals that support fewer.
+
+use std::io::IsTerminal;
+
+use crate::cli::table::Color;
+pub use crate::load::error::strip;
+
+/// Setting of `--color`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Colors that the terminal can display, from fewest to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Palette {
+    /// no escape codes at all
+    Plain,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl Palette {
+    /// Palette of the standard output
+    pub fn detect(when: When) -> Self {
+        Self::detect_on(when, std::io::stdout().is_terminal())
+    }
+
+    /// Palette of the standard error, where diagnostics are printed
+    pub fn detect_stderr(when: When) -> Self {
+        Self::detect_on(when, std::io::stderr().is_terminal())
+    }
+
+    fn detect_on(when: When, terminal: bool) -> Self {
+        let var = |name| std::env::var(name).ok().filter(|v| !v.is_empty());
+        match when {
+            When::Never => Self::Plain,
+            When::Always => Self::from_env(var("TERM").as_deref(), var("COLORTERM").as_deref()).max(Self::Ansi16),
+            When::Auto if var("NO_COLOR").is_some() || !terminal => Self::Plain,
+            When::Auto => Self::from_env(var("TERM").as_deref(), var("COLORTERM").as_deref()),
+        }
+    }
+
+    fn from_env(term: Option<&str>, colorterm: Option<&str>) -> Self {
+        match (term, colorterm) {
+            (_, Some("truecolor")) | (_, Some("24bit")) => Self::TrueColor,
+            (Some("dumb"), _) => Self::Plain,
+            (Some(term), _) if term.contains("256color") => Self::Ansi256,
+            (Some(term), _) if term.contains("direct") => Self::TrueColor,
+            _ => Self::Ansi16,
+        }
+    }
+
+    /// Escape code that sets the foreground to `color` or its closest approximation
+    pub fn foreground(self, color: Color) -> String {
+        match self {
+            Self::Plain => String::new(),
+            Self::Ansi16 => format!("\x1b[{}m", color.ansi16()),
+            Self::Ansi256 => format!("\x1b[38;5;{}m", color.ansi256()),
+            Self::TrueColor => color.to_string(),
+        }
+    }
+
+    /// Escape code that restores the default colors
+    pub fn reset(self) -> &'static str {
+        match self {
+            Self::Plain => "",
+            _ => Color::BLANK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detection() {
+        assert_eq!(Palette::from_env(Some("xterm-256color"), None), Palette::Ansi256);
+        assert_eq!(Palette::from_env(Some("xterm-256color"), Some("truecolor")), Palette::TrueColor);
+        assert_eq!(Palette::from_env(Some("xterm"), None), Palette::Ansi16);
+        assert_eq!(Palette::from_env(Some("dumb"), None), Palette::Plain);
+        assert_eq!(Palette::detect(When::Never), Palette::Plain);
+        assert!(Palette::detect(When::Always) >= Palette::Ansi16);
+    }
+
+    #[test]
+    fn stripped() {
+        let text = format!("{}red{} plain \x1b[0;91;1mbold{}", Color(255, 0, 0), Color::BLANK, Color::BLANK);
+        assert_eq!(strip(&text), "red plain bold");
+    }
+}
@@ -0,0 +1,349 @@
+use std::fmt;
+
+use crate::load::syntax::{Document, EntryKind, Field, Id, Item, Template, Term, Value, Year};
+
+/// Indentation of each level of nesting
+const INDENT: usize = 4;
+
+/// Choice between `val 5, type Food, span Day, tag "x"` and `5, Food, Day, "x"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keywords {
+    Full,
+    Short,
+}
+
+/// Printer of a `.bil` file in canonical form
+///
+/// The document is printed as is, call `Document::sort` beforehand
+/// to also reorder entries by date.
+pub struct SourceFmt<'d, 'i> {
+    doc: &'d Document<'i>,
+    keywords: Keywords,
+}
+
+impl<'d, 'i> SourceFmt<'d, 'i> {
+    pub fn from(doc: &'d Document<'i>) -> Self {
+        Self {
+            doc,
+            keywords: Keywords::Full,
+        }
+    }
+
+    pub fn with_keywords(mut self, keywords: Keywords) -> Self {
+        self.keywords = keywords;
+        self
+    }
+
+    fn render(&self) -> String {
+        let mut out = Output {
+            doc: self.doc,
+            text: String::new(),
+        };
+        for (i, item) in self.doc.items.iter().enumerate() {
+            if i > 0 {
+                out.text.push('\n');
+            }
+            match item {
+                Item::Template(templ) => self.template(&mut out, templ),
+                Item::Year(year) => self.year(&mut out, year),
+                Item::Import(id, path) => {
+                    out.leading(0, *id);
+                    out.text.push_str("import ");
+                    out.text.push_str(path);
+                    out.end_line(0, *id, None);
+                }
+            }
+        }
+        let dangling = &self.doc.trivia(self.doc.dangling).leading;
+        if !dangling.is_empty() || self.doc.tail.is_some() {
+            if !self.doc.items.is_empty() {
+                out.text.push('\n');
+            }
+            out.leading(0, self.doc.dangling);
+        }
+        if let Some(tail) = self.doc.tail {
+            out.text.push_str(tail);
+        }
+        out.text
+    }
+
+    fn year(&self, out: &mut Output, year: &Year) {
+        out.leading(0, year.id);
+        out.text.push_str(&format!("{}:", year.year));
+        // comments below a block marker go with its contents
+        out.end_line(INDENT, year.id, None);
+        for month in &year.months {
+            out.leading(INDENT, month.id);
+            out.indent(INDENT);
+            out.text.push_str(&format!("{}:", month.month));
+            out.end_line(2 * INDENT, month.id, None);
+            for day in &month.days {
+                out.leading(2 * INDENT, day.id);
+                out.indent(2 * INDENT);
+                out.text.push_str(&format!("{:02}:", day.day));
+                let trivia = self.doc.trivia(day.id);
+                // the first entry goes on the same line as the marker unless
+                // comments are in the way
+                let inline = trivia.same_line.is_none()
+                    && trivia.trailing.is_empty()
+                    && day
+                        .entries
+                        .first()
+                        .map(|e| self.doc.trivia(e.id).leading.is_empty())
+                        .unwrap_or(false);
+                if !inline {
+                    out.end_line(3 * INDENT, day.id, None);
+                }
+                for (i, entry) in day.entries.iter().enumerate() {
+                    if i == 0 && inline {
+                        out.text.push(' ');
+                    } else {
+                        out.leading(3 * INDENT, entry.id);
+                        out.indent(3 * INDENT);
+                    }
+                    match &entry.kind {
+                        EntryKind::Plain(fields) => {
+                            let fields = fields.iter().map(|f| self.field(f)).collect::<Vec<_>>();
+                            out.text.push_str(&fields.join(", "));
+                        }
+                        EntryKind::Expand(expand) => {
+                            out.text.push('!');
+                            out.text.push_str(expand.name);
+                            out.args(&expand.positional, &expand.named);
+                        }
+                    }
+                    out.text.push(';');
+                    out.end_line(3 * INDENT, entry.id, None);
+                }
+            }
+        }
+    }
+
+    fn template(&self, out: &mut Output, templ: &Template) {
+        out.leading(0, templ.id);
+        out.text.push('!');
+        out.text.push_str(templ.name);
+        for arg in &templ.positional {
+            out.text.push(' ');
+            out.text.push_str(arg);
+        }
+        out.args(&[], &templ.named);
+        out.text.push_str(" {");
+        out.end_line(0, templ.id, None);
+        let lines = templ
+            .fields
+            .iter()
+            .map(|(_, f)| format!("{:indent$}{},", "", self.field(f), indent = INDENT))
+            .collect::<Vec<_>>();
+        // comments at the end of the lines are aligned
+        let align = lines.iter().map(|l| l.chars().count() + 1).max().unwrap_or(0);
+        for ((id, _), line) in templ.fields.iter().zip(lines) {
+            out.leading(INDENT, *id);
+            out.text.push_str(&line);
+            out.end_line(INDENT, *id, Some(align));
+        }
+        out.leading(INDENT, templ.end);
+        out.text.push('}');
+        out.end_line(0, templ.end, None);
+    }
+
+    fn field(&self, field: &Field) -> String {
+        let short = self.keywords == Keywords::Short;
+        match field {
+            Field::Val { neg: false, terms } if short && terms.len() == 1 => terms_to_string(terms),
+            Field::Val { neg, terms } => {
+                format!("val {}{}", if *neg { "@Neg " } else { "" }, terms_to_string(terms))
+            }
+            Field::Type(t) if short => t.to_string(),
+            Field::Type(t) => format!("type {}", t),
+            Field::Span {
+                duration,
+                window,
+                count,
+            } => {
+                let mut s = if short { String::new() } else { String::from("span ") };
+                s.push_str(duration);
+                if let Some(window) = window {
+                    s.push_str(&format!("<{}>", window));
+                }
+                if let Some(count) = count {
+                    s.push_str(&format!(" {}", count));
+                }
+                s
+            }
+            Field::Tag(terms) if short && matches!(terms[..], [Term::Text(_)]) => terms_to_string(terms),
+            Field::Tag(terms) => format!("tag {}", terms_to_string(terms)),
+            Field::Period(p) => format!("period {}", p),
+        }
+    }
+}
+
+fn terms_to_string(terms: &[Term]) -> String {
+    terms
+        .iter()
+        .map(|t| match t {
+            Term::Amount(a) => a.to_string(),
+            Term::Text(s) => format!("\"{}\"", s),
+            Term::Arg(a) => format!("*{}", a),
+            Term::Time(t) => t.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Text being built and the comments to insert in it
+struct Output<'d, 'i> {
+    doc: &'d Document<'i>,
+    text: String,
+}
+
+impl Output<'_, '_> {
+    fn indent(&mut self, indent: usize) {
+        self.text.push_str(&" ".repeat(indent));
+    }
+
+    fn column(&self) -> usize {
+        self.text[self.text.rfind('\n').map(|i| i + 1).unwrap_or(0)..]
+            .chars()
+            .count()
+    }
+
+    /// Print the comments on the lines before `id`
+    fn leading(&mut self, indent: usize, id: Id) {
+        for c in &self.doc.trivia(id).leading {
+            self.indent(indent);
+            self.text.push_str(c);
+            self.text.push('\n');
+        }
+    }
+
+    /// Terminate the line of `id` and print the comments that follow it,
+    /// aligned with the end-of-line comment if there is one
+    fn end_line(&mut self, indent: usize, id: Id, align: Option<usize>) {
+        let trivia = self.doc.trivia(id);
+        let mut indent = align.unwrap_or(indent);
+        if let Some(c) = trivia.same_line {
+            let col = align.unwrap_or(0).max(self.column() + 1);
+            self.indent(col - self.column());
+            indent = col;
+            self.text.push_str(c);
+        }
+        self.text.push('\n');
+        for c in &trivia.trailing {
+            self.indent(indent);
+            self.text.push_str(c);
+            self.text.push('\n');
+        }
+    }
+
+    fn args(&mut self, positional: &[Value], named: &[(&str, Value)]) {
+        let value = |v: &Value| match v {
+            Value::Amount(a) => a.to_string(),
+            Value::Tag(s) => format!("\"{}\"", s),
+        };
+        for v in positional {
+            self.text.push(' ');
+            self.text.push_str(&value(v));
+        }
+        for (name, v) in named {
+            self.text.push_str(&format!(" {}={}", name, value(v)));
+        }
+    }
+}
+
+impl fmt::Display for SourceFmt<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::{error, parse, syntax, template};
+    use crate::util::date::Convention;
+
+    fn format(src: &str, keywords: Keywords) -> String {
+        let mut errs = error::Record::new();
+        let mut doc = syntax::extract("test", &mut errs, src).unwrap();
+        doc.sort();
+        SourceFmt::from(&doc).with_keywords(keywords).to_string()
+    }
+
+    fn entries(src: &str) -> Vec<String> {
+        let mut errs = error::Record::new();
+        let ast = parse::extract("test", &mut errs, src, Convention::default());
+        let (entries, _) = template::instanciate("test", &mut errs, ast, std::collections::HashMap::new(), Convention::default());
+        assert!(!errs.is_fatal());
+        let mut entries = entries.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    const FILES: &[&str] = &["examples/overview.bil", "examples/timeframes.bil"];
+
+    #[test]
+    fn idempotent() {
+        for file in FILES {
+            let src = std::fs::read_to_string(file).unwrap();
+            for &kw in &[Keywords::Full, Keywords::Short] {
+                let once = format(&src, kw);
+                assert_eq!(once, format(&once, kw));
+            }
+        }
+    }
+
+    #[test]
+    fn same_entries() {
+        for file in FILES {
+            let src = std::fs::read_to_string(file).unwrap();
+            let expected = entries(&src);
+            assert_eq!(entries(&format(&src, Keywords::Full)), expected);
+            assert_eq!(entries(&format(&src, Keywords::Short)), expected);
+        }
+    }
+
+    #[test]
+    fn comments_kept() {
+        let src = std::fs::read_to_string("examples/overview.bil").unwrap();
+        let count = |s: &str| s.matches("//").count();
+        assert_eq!(count(&format(&src, Keywords::Full)), count(&src));
+    }
+
+    #[test]
+    fn comments_indented() {
+        let src = "2020:\n// year\n  Jan:\n  // month\n    02:\n            // first\n            1, Food, Day, \"a\";\n";
+        let expected = "\
+2020:
+    // year
+    Jan:
+        // month
+        02:
+            // first
+            1, Food, Day, \"a\";
+";
+        let once = format(src, Keywords::Short);
+        assert_eq!(once, expected);
+        assert_eq!(format(&once, Keywords::Short), expected);
+    }
+
+    #[test]
+    fn sorted() {
+        let src = "2021:\n  Jan:\n    03: 1, Food, Day, \"c\";\n\
+                   2020:\n  Feb:\n    01: 1, Food, Day, \"b\";\n\
+                   2021:  // merged\n  Jan:\n    02: 1, Food, Day, \"a\";\n    03: 1, Food, Day, \"d\";\n";
+        let expected = "\
+2020:
+    Feb:
+        01: 1, Food, Day, \"b\";
+
+2021:
+    Jan:
+        // merged
+        02: 1, Food, Day, \"a\";
+        03: 1, Food, Day, \"c\";
+            1, Food, Day, \"d\";
+";
+        assert_eq!(format(src, Keywords::Short), expected);
+    }
+}
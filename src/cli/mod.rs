@@ -1,2 +1,7 @@
+pub mod color;
+pub mod format;
 pub mod plot;
+pub mod png;
+pub mod raster;
+pub mod report;
 pub mod table;
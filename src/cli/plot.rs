@@ -1,33 +1,270 @@
+use std::str::FromStr;
+
+use num_traits::FromPrimitive;
+
+use crate::cli::{
+    color::Palette,
+    raster,
+    table::{Color, Statistics},
+};
 use crate::util::{
-    date::Date,
-    entry::Amount,
+    currency::AmountFmt,
+    date::{Convention, Date, Month, Weekday},
+    entry::{Amount, Category},
     period::{Between, Minimax},
     summary::Summary,
 };
 
+/// Kinds of plots that `Plotter` can draw
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotKind {
+    /// cumulative amounts of all categories, one step per period
+    Cumulative,
+    /// expenses of each category on top of each other
+    Stacked,
+    /// income above zero and expenses below, one bar per period
+    Bars,
+    /// running difference between income and expenses
+    Balance,
+    /// share of each category in the expenses of the whole timeframe
+    Pie,
+    /// net amount of each day, one square per day in columns of weeks
+    Heatmap,
+}
+
+impl PlotKind {
+    /// Kinds in the order of `NAMES`
+    const ALL: [Self; 6] = [
+        PlotKind::Cumulative,
+        PlotKind::Stacked,
+        PlotKind::Bars,
+        PlotKind::Balance,
+        PlotKind::Pie,
+        PlotKind::Heatmap,
+    ];
+
+    /// Keywords in order, as read by `from_str`
+    pub const NAMES: [&'static str; 6] = ["cumulative", "stacked", "bars", "balance", "pie", "heatmap"];
+
+    pub fn keyword(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+}
+
+impl FromStr for PlotKind {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        Self::NAMES
+            .iter()
+            .position(|name| *name == s)
+            .map(|i| Self::ALL[i])
+            .ok_or(())
+    }
+}
+
+/// Fill color of each category
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryColors {
+    colors: Vec<String>,
+}
+
+impl Default for CategoryColors {
+    fn default() -> Self {
+        Self::colorblind()
+    }
+}
+
+impl CategoryColors {
+    /// Okabe-Ito palette, distinguishable with the common forms of color blindness
+    pub fn colorblind() -> Self {
+        Self::from(&["#009E73", "#E69F00", "#56B4E9", "#F0E442", "#0072B2", "#D55E00", "#CC79A7", "#999999"])
+    }
+
+    /// Named colors of the first versions
+    pub fn classic() -> Self {
+        Self::from(&["grey", "red", "green", "blue", "yellow", "orange", "purple", "cyan"])
+    }
+
+    fn from(colors: &[&str; Category::COUNT]) -> Self {
+        Self {
+            colors: colors.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Replace the color of `cat`
+    pub fn with<S>(mut self, cat: Category, color: S) -> Self
+    where
+        S: ToString,
+    {
+        self.colors[cat as usize] = color.to_string();
+        self
+    }
+
+    pub fn get(&self, cat: Category) -> &str {
+        &self.colors[cat as usize]
+    }
+
+    /// Approximation of the color of `cat` for the terminal,
+    /// unknown names are grey
+    pub fn rgb(&self, cat: Category) -> Color {
+        raster::color(self.get(cat)).unwrap_or(Color(128, 128, 128))
+    }
+}
+
+/// Color of the line of the balance plot
+const BALANCE_COLOR: &str = "black";
+
 /// In charge of the public interface to the plotting devices
 pub struct Plotter<'d> {
     data: &'d [Summary],
+    colors: CategoryColors,
+    title: String,
+    tooltips: bool,
+    amount_fmt: AmountFmt,
+    convention: Convention,
 }
 
 /// Recommended usage:
-/// ```
+/// ```ignore
 /// let mut cal: Calendar = unimplemented!();
 /// let lst: Vec<entry> = unimplemented!();
 /// cal.register(&lst);
-/// Plotter::from(cal.contents()).print_cumulative_plot()
+/// Plotter::from(cal.contents()).print_cumulative_plot("plots/Monthly.svg")?;
 /// ```
 impl<'d> Plotter<'d> {
     /// Wrap data to plot
     pub fn from(data: &'d [Summary]) -> Self {
-        Self { data }
+        Self {
+            data,
+            colors: CategoryColors::default(),
+            title: String::new(),
+            tooltips: false,
+            amount_fmt: AmountFmt::default(),
+            convention: Convention::default(),
+        }
+    }
+
+    pub fn with_colors(mut self, colors: CategoryColors) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Show the amounts of a period when hovering over it, for plots that have a time axis
+    pub fn with_tooltips(mut self, tooltips: bool) -> Self {
+        self.tooltips = tooltips;
+        self
+    }
+
+    /// Format of the amounts on the axes, in tooltips and in labels
+    pub fn with_amount_fmt(mut self, fmt: AmountFmt) -> Self {
+        self.amount_fmt = fmt;
+        self
+    }
+
+    /// First day of the week in the rows of the heatmap
+    pub fn with_convention(mut self, conv: Convention) -> Self {
+        self.convention = conv;
+        self
+    }
+
+    /// Text above the plot, none if empty
+    pub fn with_title<S>(mut self, title: S) -> Self
+    where
+        S: ToString,
+    {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Write the plot to `file`, nothing is written if there is no data
+    pub fn print_cumulative_plot(&self, file: &str) -> std::io::Result<()> {
+        match self.cumulative_svg() {
+            Some(svg) => std::fs::write(file, svg),
+            None => Ok(()),
+        }
+    }
+
+    /// Contents of the `.svg` file, `None` if there is no data
+    pub fn cumulative_svg(&self) -> Option<String> {
+        self.svg(PlotKind::Cumulative)
+    }
+
+    /// Contents of the `.svg` file of a plot of kind `kind`, `None` if there is no data
+    pub fn svg(&self, kind: PlotKind) -> Option<String> {
+        // graduations cannot be computed without bounds
+        if self.data.is_empty() {
+            return None;
+        }
+        let categories = |income: bool| {
+            (0..Category::COUNT)
+                .map(|i| Category::from_usize(i).unwrap())
+                .filter(|cat| income || !cat.sign())
+                .map(|cat| (cat.keyword().to_string(), self.colors.get(cat).to_string()))
+                .collect::<Vec<_>>()
+        };
+        let legend = match kind {
+            // the first category is the baseline of the others
+            PlotKind::Cumulative => categories(true).split_off(1),
+            PlotKind::Stacked | PlotKind::Pie => categories(false),
+            PlotKind::Bars => categories(true),
+            PlotKind::Balance => vec![(String::from("Balance"), String::from(BALANCE_COLOR))],
+            // shades depend on the data
+            PlotKind::Heatmap => Vec::new(),
+        };
+        let tooltips = match kind {
+            // tooltips of the heatmap are those of its squares
+            PlotKind::Pie | PlotKind::Heatmap => Vec::new(),
+            _ if !self.tooltips => Vec::new(),
+            _ => self.data.iter().map(|sum| tooltip(sum, &self.amount_fmt)).collect(),
+        };
+        let partial = match kind {
+            PlotKind::Pie | PlotKind::Heatmap => Vec::new(),
+            _ => self.data.iter().filter(|sum| sum.is_partial()).map(|sum| tooltip(sum, &self.amount_fmt).0).collect(),
+        };
+        let caption = Caption {
+            title: &self.title,
+            unit: self.amount_fmt.symbol().trim(),
+            legend,
+            tooltips,
+            partial,
+        };
+        let document = match kind {
+            PlotKind::Cumulative => self.cumulative_plot().to_range_group_drawer(&self.amount_fmt).render(&caption),
+            PlotKind::Stacked => self.stacked_plot().render(&caption),
+            PlotKind::Bars => self.bar_plot().render(&caption),
+            PlotKind::Balance => self.balance_plot().render(&caption),
+            PlotKind::Pie => self.pie_plot().render(&caption),
+            PlotKind::Heatmap => self.heatmap_plot().render(&caption),
+        };
+        document.map(|document| document.to_string())
     }
 
-    /// Launch plotting
-    pub fn print_cumulative_plot(&self, title: &str) {
-        self.cumulative_plot()
-            .to_range_group_drawer()
-            .render(&format!("{}.svg", title))
+    /// Contents of the `.png` file of a plot of kind `kind` rendered at `dpi`,
+    /// `None` if there is no data, an error if the image would be too large
+    pub fn png(&self, kind: PlotKind, dpi: f64) -> Option<Result<Vec<u8>, raster::TooLarge>> {
+        self.svg(kind).map(|svg| raster::png(&svg, dpi))
+    }
+
+    /// Cumulative plot drawn with block characters for a terminal `width` columns wide,
+    /// `None` if there is no data
+    pub fn term(&self, width: usize, palette: Palette) -> Option<String> {
+        if self.data.is_empty() {
+            return None;
+        }
+        // the first category is the baseline of the others
+        let legend = (1..Category::COUNT)
+            .map(|i| Category::from_usize(i).unwrap())
+            .map(|cat| (cat.keyword().to_string(), self.colors.rgb(cat)))
+            .collect::<Vec<_>>();
+        let plot = self
+            .cumulative_plot()
+            .to_range_group_drawer(&self.amount_fmt)
+            .render_term(&legend, width, palette);
+        if self.title.is_empty() {
+            Some(plot)
+        } else {
+            Some(format!("{}\n{}", self.title, plot))
+        }
     }
 
     /// Accumulate contained data into cumulative plot
@@ -38,6 +275,191 @@ impl<'d> Plotter<'d> {
         }
         plot
     }
+
+    /// Expenses of each category stacked, through the middle of each period
+    fn stacked_plot(&self) -> AreaDrawer {
+        let mut grad_x = Grads::new();
+        let mut grad_y = Grads::new();
+        let mut points = Vec::new();
+        for sum in self.data {
+            let Between(start, end) = sum.period();
+            grad_x.extend(start);
+            grad_x.extend(end);
+            // the first band starts from zero
+            let mut bands = vec![Amount(0)];
+            bands.extend(expenses(sum).map(|(_, amount)| amount));
+            let bands = CumulativeEntry::cumul(bands);
+            bands.extend(&mut grad_y);
+            // days are inclusive, the period ends at the start of the next day
+            let mid = (start.to_scalar() + end.to_scalar() + 1) / 2;
+            points.push((mid, bands.to_group()));
+        }
+        // flat ends so that the area covers the whole timeframe
+        let first = (self.data[0].period().0.to_scalar(), points[0].1.clone());
+        let last = (self.data[self.data.len() - 1].period().1.to_scalar() + 1, points[points.len() - 1].1.clone());
+        points.insert(0, first);
+        points.push(last);
+        AreaDrawer {
+            points,
+            grad_x: grad_x.into_grads(&self.amount_fmt),
+            grad_y: grad_y.into_grads(&self.amount_fmt),
+        }
+    }
+
+    /// Income above zero and expenses of each category stacked below zero
+    fn bar_plot(&self) -> BarDrawer {
+        let mut grad_x = Grads::new();
+        let mut grad_y = Grads::new();
+        grad_y.extend(Amount(0));
+        let mut columns = Vec::new();
+        for sum in self.data {
+            let period = sum.period();
+            period.extend(&mut grad_x);
+            let mut segments = Vec::new();
+            let (mut top, mut bottom) = (0, 0);
+            for i in 0..Category::COUNT {
+                let cat = Category::from_usize(i).unwrap();
+                let amount = sum.query(cat).to_scalar();
+                if cat.sign() {
+                    segments.push((self.colors.get(cat).to_string(), top, top + amount));
+                    top += amount;
+                } else {
+                    segments.push((self.colors.get(cat).to_string(), bottom - amount, bottom));
+                    bottom -= amount;
+                }
+            }
+            grad_y.extend(Amount(top as isize));
+            grad_y.extend(Amount(bottom as isize));
+            columns.push((period.to_range(), segments));
+        }
+        BarDrawer {
+            columns,
+            grad_x: grad_x.into_grads(&self.amount_fmt),
+            grad_y: grad_y.into_grads(&self.amount_fmt),
+        }
+    }
+
+    /// Income minus expenses, accumulated since the start of the timeframe
+    fn balance_plot(&self) -> LineDrawer {
+        let mut grad_x = Grads::new();
+        let mut grad_y = Grads::new();
+        grad_y.extend(Amount(0));
+        let mut points = vec![(self.data[0].period().0.to_scalar(), 0)];
+        let mut balance = Amount(0);
+        for sum in self.data {
+            sum.period().extend(&mut grad_x);
+            balance += net(sum);
+            grad_y.extend(balance);
+            points.push((sum.period().1.to_scalar() + 1, balance.to_scalar()));
+        }
+        LineDrawer {
+            points,
+            grad_x: grad_x.into_grads(&self.amount_fmt),
+            grad_y: grad_y.into_grads(&self.amount_fmt),
+        }
+    }
+
+    /// Total expenses of each category over all periods
+    fn pie_plot(&self) -> PieDrawer {
+        let mut slices = expenses(&self.data[0])
+            .map(|(cat, _)| (cat, Amount(0)))
+            .collect::<Vec<_>>();
+        for sum in self.data {
+            for ((_, total), (_, amount)) in slices.iter_mut().zip(expenses(sum)) {
+                *total += amount;
+            }
+        }
+        PieDrawer {
+            slices: slices
+                .into_iter()
+                .map(|(cat, total)| (cat.keyword().to_string(), self.colors.get(cat).to_string(), total))
+                .collect(),
+            amount_fmt: self.amount_fmt.clone(),
+        }
+    }
+
+    /// Net amount of each day, shaded by deciles as in tables so that spending is red,
+    /// the data is expected to come from a calendar of days
+    fn heatmap_plot(&self) -> HeatmapDrawer {
+        let mut stats = Statistics::new();
+        let mut totals = self.data.iter().map(net).filter(|a| a.0 != 0).collect::<Vec<_>>();
+        for amount in &totals {
+            stats.register(amount.0 as f64);
+        }
+        let shader = stats.make_shader();
+        let shade = |amount: Amount| {
+            if amount.0 == 0 {
+                String::from(HeatmapDrawer::EMPTY)
+            } else {
+                shader.generate(amount.0 as f64).hex()
+            }
+        };
+        // most spent, median and least spent, then the same for income
+        totals.sort();
+        let spent = totals.iter().filter(|a| a.0 < 0).collect::<Vec<_>>();
+        let earned = totals.iter().filter(|a| a.0 > 0).rev().collect::<Vec<_>>();
+        let mut legend = Vec::new();
+        for side in &[spent, earned] {
+            if let (Some(first), Some(last)) = (side.first(), side.last()) {
+                for amount in &[*first, side[side.len() / 2], *last] {
+                    let item = (self.amount_fmt.format(**amount), shade(**amount));
+                    if !legend.contains(&item) {
+                        legend.push(item);
+                    }
+                }
+            }
+        }
+        legend.push((String::from("Nothing"), String::from(HeatmapDrawer::EMPTY)));
+        HeatmapDrawer {
+            days: self
+                .data
+                .iter()
+                .map(|sum| (sum.period().0, net(sum), shade(net(sum))))
+                .collect(),
+            legend,
+            tooltips: self.tooltips,
+            amount_fmt: self.amount_fmt.clone(),
+            convention: self.convention,
+        }
+    }
+}
+
+/// Bounds of the period of `sum` and text that details its amounts
+fn tooltip(sum: &Summary, fmt: &AmountFmt) -> ((i64, i64), String) {
+    let Between(start, end) = sum.period();
+    let mut text = format!("{} to {}", start, end);
+    if sum.is_partial() {
+        text.push_str(" (partial)");
+    }
+    for i in 0..Category::COUNT {
+        let cat = Category::from_usize(i).unwrap();
+        text.push_str(&format!("\n{}: {}", cat.keyword(), fmt.format(sum.query(cat))));
+    }
+    text.push_str(&format!("\nTotal: {}", fmt.format(sum.total())));
+    // days are inclusive, the period ends at the start of the next day
+    ((start.to_scalar(), end.to_scalar() + 1), text)
+}
+
+/// Income minus expenses
+fn net(sum: &Summary) -> Amount {
+    let mut net = Amount(0);
+    for i in 0..Category::COUNT {
+        let cat = Category::from_usize(i).unwrap();
+        if cat.sign() {
+            net += sum.query(cat);
+        } else {
+            net += -sum.query(cat);
+        }
+    }
+    net
+}
+
+/// Amounts of the categories that are not income
+fn expenses(sum: &Summary) -> impl Iterator<Item = (Category, Amount)> + '_ {
+    (0..Category::COUNT)
+        .map(|i| Category::from_usize(i).unwrap())
+        .filter(|cat| !cat.sign())
+        .map(move |cat| (cat, sum.query(cat)))
 }
 
 /// Holds data for bounds of data to graduate
@@ -72,16 +494,33 @@ where
 
 impl<T> Grads<T>
 where
-    T: ToString + Scalar + Hierarchical,
+    T: Label + Scalar + Hierarchical,
 {
-    fn into_grads(self) -> Vec<(i64, String)> {
+    fn into_grads(self, fmt: &AmountFmt) -> Vec<(i64, String)> {
         T::hierarchy(self.lower, self.upper)
             .into_iter()
-            .map(|x| (x.to_scalar(), x.to_string()))
+            .map(|x| (x.to_scalar(), x.label(fmt)))
             .collect::<Vec<_>>()
     }
 }
 
+/// Text of a graduation
+pub trait Label {
+    fn label(&self, fmt: &AmountFmt) -> String;
+}
+
+impl Label for Amount {
+    fn label(&self, fmt: &AmountFmt) -> String {
+        fmt.format(*self)
+    }
+}
+
+impl Label for Date {
+    fn label(&self, _: &AmountFmt) -> String {
+        self.to_string()
+    }
+}
+
 pub trait GradExtend {
     type Item;
     fn extend(&self, grads: &mut Grads<Self::Item>);
@@ -159,6 +598,12 @@ pub struct Plot<X, Y> {
     data: Vec<(X, Y)>,
 }
 
+impl<X, Y> Default for Plot<X, Y> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<X, Y> Plot<X, Y> {
     /// Empty plotter
     pub fn new() -> Self {
@@ -289,10 +734,10 @@ impl<X, Y> Plot<X, Y>
 where
     X: ScalarRange + GradExtend,
     Y: ScalarGroup + GradExtend,
-    <X as GradExtend>::Item: ToString + Scalar + Minimax + Hierarchical,
-    <Y as GradExtend>::Item: ToString + Scalar + Minimax + Hierarchical,
+    <X as GradExtend>::Item: Label + Scalar + Minimax + Hierarchical,
+    <Y as GradExtend>::Item: Label + Scalar + Minimax + Hierarchical,
 {
-    fn to_range_group_drawer(&self) -> RangeGroupDrawer {
+    fn to_range_group_drawer(&self, fmt: &AmountFmt) -> RangeGroupDrawer {
         let mut points = Vec::new();
         let mut grad_x = Grads::new();
         let mut grad_y = Grads::new();
@@ -305,8 +750,8 @@ where
         }
         RangeGroupDrawer {
             points,
-            grad_x: grad_x.into_grads(),
-            grad_y: grad_y.into_grads(),
+            grad_x: grad_x.into_grads(fmt),
+            grad_y: grad_y.into_grads(fmt),
         }
     }
 }
@@ -370,11 +815,11 @@ impl Dimensions {
     }
 
     fn resize_x(&self, x: i64) -> f64 {
-        (x - self.min_x) as f64 / self.delta_x as f64 * self.view_width
+        (x - self.min_x) as f64 / self.delta_x.max(1) as f64 * self.view_width
     }
 
     fn resize_y(&self, y: i64) -> f64 {
-        (self.max_y - y) as f64 / self.delta_y as f64 * self.view_height
+        (self.max_y - y) as f64 / self.delta_y.max(1) as f64 * self.view_height
     }
 
     fn margin(&self) -> f64 {
@@ -399,7 +844,7 @@ struct RangeGroupDrawer {
 
 use svg::{
     node,
-    node::element::{path::Data, Line as SvgLine, Path, Text},
+    node::element::{path::Data, Line as SvgLine, Path, Rectangle, Text, Title},
     Document,
 };
 
@@ -442,7 +887,7 @@ impl<'dim> Line<'dim> {
 }
 
 impl RangeGroupDrawer {
-    fn render(&self, file: &str) {
+    fn render(&self, caption: &Caption) -> Option<Document> {
         // configure dimensions with extremal values
         let dim = Dimensions::new().with_data(
             self.points
@@ -451,125 +896,108 @@ impl RangeGroupDrawer {
         );
         // plot columns one by one
         if self.points.is_empty() {
-            return;
+            return None;
         }
         let paths = self
             .extract_groups(&dim)
             .into_iter()
-            .enumerate()
-            .map(|(i, gr)| Path::new().set("fill", COLORS[i]).set("d", gr.close()));
-        let yaxis = Line::black(&dim)
-            .set_x1(dim.min_x, 0.0)
-            .set_x2(dim.min_x, 0.0)
-            .set_y1(dim.max_y, -dim.margin())
-            .set_y2(dim.min_y, dim.margin())
-            .into_svg_line();
-        let ylarrow = Line::black(&dim)
-            .set_x1(dim.min_x, 0.0)
-            .set_x2(dim.min_x, dim.margin_small())
-            .set_y1(dim.max_y, -dim.margin())
-            .set_y2(dim.max_y, -dim.margin_small())
-            .into_svg_line();
-        let yrarrow = Line::black(&dim)
-            .set_x1(dim.min_x, 0.0)
-            .set_x2(dim.min_x, -dim.margin_small())
-            .set_y1(dim.max_y, -dim.margin())
-            .set_y2(dim.max_y, -dim.margin_small())
-            .into_svg_line();
-        let ygrad = self.grad_y.iter().map(|(n, txt)| {
-            (
-                Line::black(&dim)
-                    .set_x1(dim.min_x, 0.0)
-                    .set_x2(dim.min_x, -dim.margin_small())
-                    .set_y1(*n, 0.0)
-                    .set_y2(*n, 0.0)
-                    .into_svg_line(),
-                Text::new()
-                    .set("x", dim.resize_x(dim.min_x) - dim.margin())
-                    .set("y", dim.resize_y(*n) + dim.margin_small())
-                    .set("stroke", "black")
-                    .set("text-anchor", "end")
-                    .set("stroke-width", dim.stroke_width)
-                    .add(node::Text::new(txt)),
-            )
-        });
-        let xaxis = Line::black(&dim)
-            .set_x1(dim.min_x, 0.0)
-            .set_x2(dim.max_x, dim.margin())
-            .set_y1(0, 0.0)
-            .set_y2(0, 0.0)
-            .into_svg_line();
-        let xlarrow = Line::black(&dim)
-            .set_x1(dim.max_x, dim.margin())
-            .set_x2(dim.max_x, dim.margin_small())
-            .set_y1(0, 0.0)
-            .set_y2(0, dim.margin_small())
-            .into_svg_line();
-        let xrarrow = Line::black(&dim)
-            .set_x1(dim.max_x, dim.margin())
-            .set_x2(dim.max_x, dim.margin_small())
-            .set_y1(0, 0.0)
-            .set_y2(0, -dim.margin_small())
-            .into_svg_line();
-        let hlines = self.grad_y.iter().map(|(n, _)| {
-            Line::with_color(&dim, "grey")
-                .set_x1(dim.min_x, 0.0)
-                .set_x2(dim.max_x, 0.0)
-                .set_y1(*n, 0.0)
-                .set_y2(*n, 0.0)
-                .into_svg_line()
-                .set("stroke-dasharray", "15,20")
-                .set("stroke-dashoffset", "15")
-                .set("stroke-width", 1)
-        });
-        let xgrad = self.grad_x.iter().map(|(n, txt)| {
-            (
-                Line::black(&dim)
-                    .set_x1(*n, 0.0)
-                    .set_x2(*n, 0.0)
-                    .set_y1(0, 0.0)
-                    .set_y2(0, dim.margin_small())
-                    .into_svg_line(),
-                Text::new()
-                    .set(
-                        "transform",
-                        format!(
-                            "rotate(40, {x}, {y}) translate({x} {y}) translate(10 20)",
-                            x = dim.resize_x(*n) + dim.margin_small(),
-                            y = dim.resize_y(0) - dim.margin_small()
-                        ),
-                    )
-                    .set("stroke", "black")
-                    .set("stroke-width", dim.stroke_width)
-                    .add(node::Text::new(txt)),
-            )
-        });
-        let document = paths
-            .into_iter()
-            .fold(Document::new(), |doc, path| doc.add(path));
-        let document = hlines
-            .into_iter()
-            .fold(document, |doc, hline| doc.add(hline));
-        let document = ygrad
-            .into_iter()
-            .chain(xgrad.into_iter())
-            .fold(document, |doc, (path, text)| doc.add(path).add(text))
-            .add(yaxis)
-            .add(ylarrow)
-            .add(yrarrow)
-            .add(xlarrow)
-            .add(xrarrow)
-            .add(xaxis)
-            .set(
-                "viewBox",
-                (
-                    -dim.margin_big(),
-                    -dim.margin_big(),
-                    dim.view_width + dim.margin_big() * 2.0,
-                    dim.view_height + dim.margin_big() * 2.0,
-                ),
-            );
-        svg::save(file, &document).unwrap();
+            .zip(&caption.legend)
+            .map(|(gr, (_, color))| Path::new().set("fill", color.as_str()).set("d", gr.close()));
+        let document = paths.fold(Document::new(), |doc, path| doc.add(path));
+        Some(caption.frame(axes(document, &dim, &self.grad_x, &self.grad_y, caption.unit), &dim))
+    }
+
+    /// Bands drawn with block characters, one row is split in eighths at the top of the stack.
+    /// Without colors the bands are told apart by their shade.
+    fn render_term(&self, legend: &[(String, Color)], width: usize, palette: Palette) -> String {
+        const HEIGHT: usize = 20;
+        const SHADES: &[char] = &['█', '▓', '▒', '░', '#', '%', '+', ':'];
+        let labels = self.grad_y.iter().map(|(_, txt)| txt.chars().count()).max().unwrap_or(0);
+        // leave room for the labels and the axis
+        let columns = width.saturating_sub(labels + 1).max(10);
+        let first = self.points[0].0 .0;
+        // days are inclusive, the last period ends at the start of the next day
+        let last = self.points[self.points.len() - 1].0 .1 + 1;
+        let values = self.points.iter().flat_map(|(_, points)| points.iter().copied());
+        let top = values.clone().chain(std::iter::once(0)).max().unwrap();
+        let bottom = values.chain(std::iter::once(0)).min().unwrap();
+        let dy = (top - bottom).max(1) as f64 / HEIGHT as f64;
+        // highest stack of the periods that a column overlaps, so that peaks are not missed
+        let column_of = |x: i64| ((x - first) as f64 / (last - first) as f64 * columns as f64) as usize;
+        let day_of = |c: usize| first as f64 + c as f64 * (last - first) as f64 / columns as f64;
+        let stacks = (0..columns)
+            .map(|c| {
+                self.points
+                    .iter()
+                    .filter(|((start, end), _)| (*start as f64) < day_of(c + 1) && day_of(c) < (*end + 1) as f64)
+                    .map(|(_, points)| &points[..])
+                    .max_by_key(|points| points.last().copied())
+            })
+            .collect::<Vec<_>>();
+        let row_of = |y: i64| (((top - y) as f64 / dy) as usize).min(HEIGHT - 1);
+        let glyph = |band: usize, c: char| match (palette, c) {
+            (Palette::Plain, '█') => SHADES[band % SHADES.len()],
+            _ => c,
+        };
+        let mut out = String::new();
+        for row in 0..HEIGHT {
+            let hi = top as f64 - row as f64 * dy;
+            match self.grad_y.iter().rev().find(|(y, _)| row_of(*y) == row) {
+                Some((_, txt)) => out.push_str(&format!("{:>w$}┤", txt, w = labels)),
+                None => out.push_str(&format!("{:w$}│", "", w = labels)),
+            }
+            let mut current = None;
+            for stack in &stacks {
+                match stack.and_then(|stack| term_cell(stack, hi - dy, hi)) {
+                    Some((band, c)) => {
+                        if current != Some(band) {
+                            out.push_str(&palette.foreground(legend[band].1));
+                            current = Some(band);
+                        }
+                        out.push(glyph(band, c));
+                    }
+                    None => out.push(' '),
+                }
+            }
+            out.push_str(palette.reset());
+            out.push('\n');
+        }
+        // X axis, with as many dates as fit without overlapping
+        let mut axis = vec!['─'; columns];
+        let mut dates = vec![' '; columns];
+        let mut free = 0;
+        for (x, txt) in &self.grad_x {
+            let col = column_of(*x).min(columns - 1);
+            if col >= free && col + txt.chars().count() <= columns {
+                axis[col] = '┬';
+                for (i, c) in txt.chars().enumerate() {
+                    dates[col + i] = c;
+                }
+                free = col + txt.chars().count() + 1;
+            }
+        }
+        out.push_str(&format!("{:w$}└{}\n", "", axis.into_iter().collect::<String>(), w = labels));
+        out.push_str(&format!("{:w$} {}\n", "", dates.into_iter().collect::<String>().trim_end(), w = labels));
+        // legend, wrapped to the width of the plot
+        let mut line = String::new();
+        let mut length = 0;
+        for (band, (label, color)) in legend.iter().enumerate() {
+            let item = label.chars().count() + 2;
+            if length > 0 && length + 2 + item > columns {
+                out.push_str(&format!("{:w$} {}\n", "", line, w = labels));
+                line.clear();
+                length = 0;
+            }
+            if length > 0 {
+                line.push_str("  ");
+                length += 2;
+            }
+            let mark = format!("{}{}{}", palette.foreground(*color), glyph(band, '█'), palette.reset());
+            line.push_str(&format!("{} {}", mark, label));
+            length += item;
+        }
+        out.push_str(&format!("{:w$} {}\n", "", line, w = labels));
+        out
     }
 
     fn extract_groups(&self, dim: &Dimensions) -> Vec<Data> {
@@ -625,4 +1053,655 @@ impl RangeGroupDrawer {
     }
 }
 
-const COLORS: &[&str] = &["red", "green", "blue", "yellow", "orange", "purple", "cyan"];
+/// Bands on top of each other, linearly interpolated between points
+#[derive(Debug)]
+struct AreaDrawer {
+    /// band `i` lies between values `i` and `i + 1` of each point
+    points: Vec<(i64, Vec<i64>)>,
+    grad_x: Vec<(i64, String)>,
+    grad_y: Vec<(i64, String)>,
+}
+
+impl AreaDrawer {
+    /// The colors of the bands are those of the legend
+    fn render(&self, caption: &Caption) -> Option<Document> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let dim = Dimensions::new().with_data(self.points.iter().map(|(x, ys)| ([x], ys)));
+        let document = caption.legend.iter().enumerate().fold(Document::new(), |doc, (i, (_, color))| {
+            let lower = self
+                .points
+                .iter()
+                .map(|(x, ys)| (dim.resize_x(*x), dim.resize_y(ys[i])));
+            let upper = self
+                .points
+                .iter()
+                .rev()
+                .map(|(x, ys)| (dim.resize_x(*x), dim.resize_y(ys[i + 1])));
+            let mut points = lower.chain(upper);
+            let start = Data::new().move_to(points.next().unwrap());
+            let data = points.fold(start, |data, point| data.line_to(point));
+            doc.add(Path::new().set("fill", color.as_str()).set("d", data.close()))
+        });
+        Some(caption.frame(axes(document, &dim, &self.grad_x, &self.grad_y, caption.unit), &dim))
+    }
+}
+
+/// Color, lower and upper bound of a rectangle
+type Segment = (String, i64, i64);
+
+/// Columns of stacked rectangles, one column per period
+#[derive(Debug)]
+struct BarDrawer {
+    columns: Vec<((i64, i64), Vec<Segment>)>,
+    grad_x: Vec<(i64, String)>,
+    grad_y: Vec<(i64, String)>,
+}
+
+impl BarDrawer {
+    fn render(&self, caption: &Caption) -> Option<Document> {
+        if self.columns.is_empty() {
+            return None;
+        }
+        let dim = Dimensions::new().with_data(self.columns.iter().map(|((start, end), segments)| {
+            (
+                [start, end],
+                segments.iter().flat_map(|(_, lo, hi)| [lo, hi]),
+            )
+        }));
+        let document = self.columns.iter().fold(Document::new(), |doc, ((start, end), segments)| {
+            // leave some space between consecutive columns
+            let left = dim.resize_x(*start);
+            let right = dim.resize_x(*end) + dim.atomic_width;
+            let gap = (right - left) * 0.1;
+            segments
+                .iter()
+                .filter(|(_, lo, hi)| lo != hi)
+                .fold(doc, |doc, (color, lo, hi)| {
+                    let data = Data::new()
+                        .move_to((left + gap, dim.resize_y(*lo)))
+                        .line_to((right - gap, dim.resize_y(*lo)))
+                        .line_to((right - gap, dim.resize_y(*hi)))
+                        .line_to((left + gap, dim.resize_y(*hi)))
+                        .close();
+                    doc.add(Path::new().set("fill", color.as_str()).set("d", data))
+                })
+        });
+        Some(caption.frame(axes(document, &dim, &self.grad_x, &self.grad_y, caption.unit), &dim))
+    }
+}
+
+/// Single line through all points
+#[derive(Debug)]
+struct LineDrawer {
+    points: Vec<(i64, i64)>,
+    grad_x: Vec<(i64, String)>,
+    grad_y: Vec<(i64, String)>,
+}
+
+impl LineDrawer {
+    fn render(&self, caption: &Caption) -> Option<Document> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let dim = Dimensions::new().with_data(self.points.iter().map(|(x, y)| ([x], [y])));
+        let mut points = self
+            .points
+            .iter()
+            .map(|(x, y)| (dim.resize_x(*x), dim.resize_y(*y)));
+        let start = Data::new().move_to(points.next().unwrap());
+        let data = points.fold(start, |data, point| data.line_to(point));
+        let line = Path::new()
+            .set("fill", "none")
+            .set("stroke", BALANCE_COLOR)
+            .set("stroke-width", dim.stroke_width * 2.0)
+            .set("d", data);
+        let document = axes(Document::new().add(line), &dim, &self.grad_x, &self.grad_y, caption.unit);
+        Some(caption.frame(document, &dim))
+    }
+}
+
+/// Ring divided in proportion to the amounts
+#[derive(Debug)]
+struct PieDrawer {
+    /// label, color and amount of each slice
+    slices: Vec<(String, String, Amount)>,
+    amount_fmt: AmountFmt,
+}
+
+impl PieDrawer {
+    fn render(&self, caption: &Caption) -> Option<Document> {
+        let total = self.slices.iter().map(|(_, _, amount)| amount.0.max(0)).sum::<isize>();
+        if total == 0 {
+            return None;
+        }
+        let dim = Dimensions::new();
+        let (cx, cy) = (dim.view_width / 2.0, dim.view_height / 2.0);
+        let outer = dim.view_height / 2.0 - dim.margin_big();
+        let inner = outer * 0.55;
+        let at = |radius: f64, angle: f64| (cx + radius * angle.cos(), cy + radius * angle.sin());
+        // clockwise from the top
+        let mut angle = -std::f64::consts::FRAC_PI_2;
+        let mut document = Document::new();
+        for (label, color, amount) in self.slices.iter().filter(|(_, _, amount)| amount.0 > 0) {
+            let share = amount.0 as f64 / total as f64;
+            // an arc that ends where it starts is not drawn at all
+            let sweep = (share * std::f64::consts::TAU).min(std::f64::consts::TAU - 1e-4);
+            let (start, end) = (angle, angle + sweep);
+            let large = if sweep > std::f64::consts::PI { 1 } else { 0 };
+            let (ox, oy) = at(outer, end);
+            let (ix, iy) = at(inner, start);
+            let data = Data::new()
+                .move_to(at(outer, start))
+                .elliptical_arc_to((outer, outer, 0, large, 1, ox, oy))
+                .line_to(at(inner, end))
+                .elliptical_arc_to((inner, inner, 0, large, 0, ix, iy))
+                .close();
+            document = document.add(Path::new().set("fill", color.as_str()).set("d", data));
+            let middle = (start + end) / 2.0;
+            let (tx, ty) = at(outer + dim.margin(), middle);
+            let text = Text::new()
+                .set("x", tx)
+                .set("y", ty)
+                .set("text-anchor", if middle.cos() < 0.0 { "end" } else { "start" })
+                .add(node::Text::new(format!("{} {:.0}%", label, share * 100.0)));
+            document = document.add(text);
+            angle = end;
+        }
+        let total = Text::new()
+            .set("x", cx)
+            .set("y", cy)
+            .set("text-anchor", "middle")
+            .add(node::Text::new(self.amount_fmt.format(Amount(total))));
+        Some(caption.frame(document.add(total), &dim))
+    }
+}
+
+/// One square per day, a block of seven rows per year
+#[derive(Debug)]
+struct HeatmapDrawer {
+    /// day, net amount and color
+    days: Vec<(Date, Amount, String)>,
+    /// amounts shown in the legend and their color
+    legend: Vec<(String, String)>,
+    tooltips: bool,
+    amount_fmt: AmountFmt,
+    convention: Convention,
+}
+
+impl HeatmapDrawer {
+    /// Distance between the corners of neighboring squares
+    const CELL: f64 = 16.0;
+    /// Side of a square
+    const SQUARE: f64 = 13.0;
+    /// Color of days with no net amount
+    const EMPTY: &'static str = "#ebedf0";
+
+    fn render(&self, caption: &Caption) -> Option<Document> {
+        let first = self.days.first()?.0.year();
+        let last = self.days.last()?.0.year();
+        // a row for the months and one to separate years
+        let block = Self::CELL * 9.0;
+        let dim = Dimensions {
+            // a year touches at most 54 weeks
+            view_width: Self::CELL * 54.0,
+            view_height: block * (last - first + 1) as f64 - Self::CELL,
+            ..Dimensions::new()
+        };
+        let conv = self.convention;
+        let origin = |year: u16| conv.start_of_week(Date::from(year as usize, Month::Jan, 1).unwrap());
+        // rows are counted from the first day of the week
+        let week = conv.week_start() as usize;
+        let mut document = Document::new();
+        for year in first..=last {
+            let top = block * (year - first) as f64;
+            document = document.add(
+                Text::new()
+                    .set("x", -dim.margin_small())
+                    .set("y", top + Self::SQUARE)
+                    .set("text-anchor", "end")
+                    .set("font-weight", "bold")
+                    .add(node::Text::new(year.to_string())),
+            );
+            for row in [0, 2, 4] {
+                let name = Weekday::from_usize((week + row) % 7).unwrap();
+                document = document.add(
+                    Text::new()
+                        .set("x", -dim.margin_small())
+                        .set("y", top + Self::CELL * (row + 1) as f64 + Self::SQUARE)
+                        .set("text-anchor", "end")
+                        .set("font-size", 12)
+                        .add(node::Text::new(name.to_string())),
+                );
+            }
+            let mut month = Month::Jan;
+            loop {
+                // above the first week that starts in the month
+                let start = Date::from(year as usize, month, 1).unwrap();
+                let column = (start.index() - origin(year).index()).div_ceil(7);
+                document = document.add(
+                    Text::new()
+                        .set("x", Self::CELL * column as f64)
+                        .set("y", top + Self::SQUARE)
+                        .set("font-size", 12)
+                        .add(node::Text::new(month.to_string())),
+                );
+                if month == Month::Dec {
+                    break;
+                }
+                month = month.next();
+            }
+        }
+        for (day, amount, color) in &self.days {
+            let column = (conv.start_of_week(*day).index() - origin(day.year()).index()) / 7;
+            let row = (day.weekday() as usize + 7 - week) % 7;
+            let top = block * (day.year() - first) as f64 + Self::CELL;
+            let mut square = Rectangle::new()
+                .set("x", Self::CELL * column as f64)
+                .set("y", top + Self::CELL * row as f64)
+                .set("width", Self::SQUARE)
+                .set("height", Self::SQUARE)
+                .set("fill", color.as_str());
+            if self.tooltips {
+                let text = format!("{} {}: {}", day.weekday(), day, self.amount_fmt.format(*amount));
+                square = square.add(Title::new().add(node::Text::new(text)));
+            }
+            document = document.add(square);
+        }
+        let caption = Caption {
+            title: caption.title,
+            unit: caption.unit,
+            legend: self.legend.clone(),
+            tooltips: Vec::new(),
+            partial: Vec::new(),
+        };
+        Some(caption.frame(document, &dim))
+    }
+}
+
+/// Band at height `y` of a stack of cumulative values
+fn term_band(stack: &[i64], y: f64) -> Option<usize> {
+    stack.windows(2).position(|w| w[0] as f64 <= y && y < w[1] as f64)
+}
+
+/// Band and character of the cell between heights `lo` and `hi`, `None` if it is empty
+fn term_cell(stack: &[i64], lo: f64, hi: f64) -> Option<(usize, char)> {
+    const EIGHTHS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let peak = *stack.last()? as f64;
+    if peak <= lo {
+        None
+    } else if peak < hi {
+        let eighths = ((peak - lo) / (hi - lo) * 8.0).round() as usize;
+        let band = term_band(stack, (lo + peak) / 2.0)?;
+        Some((band, EIGHTHS[eighths.checked_sub(1)?]))
+    } else {
+        term_band(stack, (lo + hi) / 2.0).map(|band| (band, '█'))
+    }
+}
+
+/// Title and legend of a plot
+struct Caption<'a> {
+    title: &'a str,
+    /// currency symbol shown next to the title of the Y axis
+    unit: &'a str,
+    /// label and color of each item
+    legend: Vec<(String, String)>,
+    /// text shown when hovering over a range of the X axis
+    tooltips: Vec<((i64, i64), String)>,
+    /// ranges of the X axis that are faded out because their period is incomplete
+    partial: Vec<(i64, i64)>,
+}
+
+impl Caption<'_> {
+    /// Width of the legend box
+    const LEGEND_WIDTH: f64 = 180.0;
+    /// Height of an item of the legend
+    const LEGEND_ROW: f64 = 30.0;
+
+    /// Add the title above the plot, the legend to its right and the tooltips on top, then frame the document
+    fn frame(&self, document: Document, dim: &Dimensions) -> Document {
+        let mut document = document;
+        for (start, end) in &self.partial {
+            let left = dim.resize_x(*start);
+            let right = dim.resize_x(*end).min(dim.view_width + dim.atomic_width);
+            document = document.add(
+                Rectangle::new()
+                    .set("class", "partial")
+                    .set("x", left)
+                    .set("y", 0)
+                    .set("width", right - left)
+                    .set("height", dim.view_height)
+                    .set("fill", "white")
+                    .set("fill-opacity", 0.5),
+            );
+        }
+        for ((start, end), text) in &self.tooltips {
+            let left = dim.resize_x(*start);
+            let right = dim.resize_x(*end).min(dim.view_width + dim.atomic_width);
+            document = document.add(
+                Rectangle::new()
+                    .set("class", "bucket")
+                    .set("x", left)
+                    .set("y", 0)
+                    .set("width", right - left)
+                    .set("height", dim.view_height)
+                    .set("fill", "black")
+                    .set("fill-opacity", 0)
+                    .add(Title::new().add(node::Text::new(text.as_str()))),
+            );
+        }
+        if !self.title.is_empty() {
+            document = document.add(
+                Text::new()
+                    .set("x", dim.view_width / 2.0)
+                    .set("y", -dim.margin_big() + dim.margin())
+                    .set("text-anchor", "middle")
+                    .set("font-size", 24)
+                    .set("font-weight", "bold")
+                    .add(node::Text::new(self.title)),
+            );
+        }
+        let left = dim.view_width + dim.margin_big();
+        let height = self.legend.len() as f64 * Self::LEGEND_ROW + dim.margin();
+        document = document.add(
+            Rectangle::new()
+                .set("x", left)
+                .set("y", 0)
+                .set("width", Self::LEGEND_WIDTH)
+                .set("height", height)
+                .set("fill", "white")
+                .set("stroke", "black"),
+        );
+        for (i, (label, color)) in self.legend.iter().enumerate() {
+            let top = dim.margin_small() + i as f64 * Self::LEGEND_ROW;
+            document = document
+                .add(
+                    Rectangle::new()
+                        .set("x", left + dim.margin_small())
+                        .set("y", top)
+                        .set("width", dim.margin())
+                        .set("height", dim.margin())
+                        .set("fill", color.as_str()),
+                )
+                .add(
+                    Text::new()
+                        .set("x", left + dim.margin() * 2.0)
+                        .set("y", top + dim.margin() * 0.8)
+                        .add(node::Text::new(label.as_str())),
+                );
+        }
+        document.set(
+            "viewBox",
+            (
+                -dim.margin_big(),
+                -dim.margin_big(),
+                dim.view_width + dim.margin_big() * 3.0 + Self::LEGEND_WIDTH,
+                dim.view_height + dim.margin_big() * 2.0,
+            ),
+        )
+    }
+}
+
+/// Add axes, graduations, horizontal guides and titles of the axes to `document`,
+/// dates are on the X axis and amounts on the Y axis
+fn axes(document: Document, dim: &Dimensions, grad_x: &[(i64, String)], grad_y: &[(i64, String)], unit: &str) -> Document {
+    let yaxis = Line::black(dim)
+        .set_x1(dim.min_x, 0.0)
+        .set_x2(dim.min_x, 0.0)
+        .set_y1(dim.max_y, -dim.margin())
+        .set_y2(dim.min_y, dim.margin())
+        .into_svg_line();
+    let ylarrow = Line::black(dim)
+        .set_x1(dim.min_x, 0.0)
+        .set_x2(dim.min_x, dim.margin_small())
+        .set_y1(dim.max_y, -dim.margin())
+        .set_y2(dim.max_y, -dim.margin_small())
+        .into_svg_line();
+    let yrarrow = Line::black(dim)
+        .set_x1(dim.min_x, 0.0)
+        .set_x2(dim.min_x, -dim.margin_small())
+        .set_y1(dim.max_y, -dim.margin())
+        .set_y2(dim.max_y, -dim.margin_small())
+        .into_svg_line();
+    let ygrad = grad_y.iter().map(|(n, txt)| {
+        (
+            Line::black(dim)
+                .set_x1(dim.min_x, 0.0)
+                .set_x2(dim.min_x, -dim.margin_small())
+                .set_y1(*n, 0.0)
+                .set_y2(*n, 0.0)
+                .into_svg_line(),
+            Text::new()
+                .set("x", dim.resize_x(dim.min_x) - dim.margin())
+                .set("y", dim.resize_y(*n) + dim.margin_small())
+                .set("stroke", "black")
+                .set("text-anchor", "end")
+                .set("stroke-width", dim.stroke_width)
+                .add(node::Text::new(txt)),
+        )
+    });
+    let xaxis = Line::black(dim)
+        .set_x1(dim.min_x, 0.0)
+        .set_x2(dim.max_x, dim.margin())
+        .set_y1(0, 0.0)
+        .set_y2(0, 0.0)
+        .into_svg_line();
+    let xlarrow = Line::black(dim)
+        .set_x1(dim.max_x, dim.margin())
+        .set_x2(dim.max_x, dim.margin_small())
+        .set_y1(0, 0.0)
+        .set_y2(0, dim.margin_small())
+        .into_svg_line();
+    let xrarrow = Line::black(dim)
+        .set_x1(dim.max_x, dim.margin())
+        .set_x2(dim.max_x, dim.margin_small())
+        .set_y1(0, 0.0)
+        .set_y2(0, -dim.margin_small())
+        .into_svg_line();
+    let hlines = grad_y.iter().map(|(n, _)| {
+        Line::with_color(dim, "grey")
+            .set_x1(dim.min_x, 0.0)
+            .set_x2(dim.max_x, 0.0)
+            .set_y1(*n, 0.0)
+            .set_y2(*n, 0.0)
+            .into_svg_line()
+            .set("stroke-dasharray", "15,20")
+            .set("stroke-dashoffset", "15")
+            .set("stroke-width", 1)
+    });
+    let xgrad = grad_x.iter().map(|(n, txt)| {
+        (
+            Line::black(dim)
+                .set_x1(*n, 0.0)
+                .set_x2(*n, 0.0)
+                .set_y1(0, 0.0)
+                .set_y2(0, dim.margin_small())
+                .into_svg_line(),
+            Text::new()
+                .set(
+                    "transform",
+                    format!(
+                        "rotate(40, {x}, {y}) translate({x} {y}) translate(10 20)",
+                        x = dim.resize_x(*n) + dim.margin_small(),
+                        y = dim.resize_y(0) - dim.margin_small()
+                    ),
+                )
+                .set("stroke", "black")
+                .set("stroke-width", dim.stroke_width)
+                .add(node::Text::new(txt)),
+        )
+    });
+    let ytitle = Text::new()
+        .set("x", dim.resize_x(dim.min_x) + dim.margin())
+        .set("y", dim.resize_y(dim.max_y) - dim.margin())
+        .set("font-weight", "bold")
+        .add(node::Text::new(if unit.is_empty() {
+            String::from("Amount")
+        } else {
+            format!("Amount ({})", unit)
+        }));
+    let xtitle = Text::new()
+        .set("x", dim.resize_x(dim.max_x) + dim.margin())
+        .set("y", dim.resize_y(0) - dim.margin())
+        .set("text-anchor", "end")
+        .set("font-weight", "bold")
+        .add(node::Text::new("Date"));
+    let document = hlines
+        .into_iter()
+        .fold(document, |doc, hline| doc.add(hline));
+    ygrad
+        .into_iter()
+        .chain(xgrad)
+        .fold(document, |doc, (path, text)| doc.add(path).add(text))
+        .add(yaxis)
+        .add(ylarrow)
+        .add(yrarrow)
+        .add(xlarrow)
+        .add(xrarrow)
+        .add(xaxis)
+        .add(ytitle)
+        .add(xtitle)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::date::Month::*;
+
+    #[test]
+    fn svg_contents() {
+        assert!(Plotter::from(&[]).cumulative_svg().is_none());
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let data = vec![
+            Summary::from_period(Between(dt(Jan, 1), dt(Jan, 31))),
+            Summary::from_period(Between(dt(Feb, 1), dt(Feb, 28))),
+        ];
+        let svg = Plotter::from(&data).cumulative_svg().unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(Plotter::from(&data).print_cumulative_plot("/nonexistent/dir/plot.svg").is_err());
+    }
+
+    #[test]
+    fn kinds() {
+        use crate::util::entry::{Entry, Tag};
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let mut data = vec![
+            Summary::from_period(Between(dt(Jan, 1), dt(Jan, 31))),
+            Summary::from_period(Between(dt(Feb, 1), dt(Feb, 28))),
+        ];
+        let entry = |val, cat, m| Entry::from(Amount(val), cat, Between(dt(m, 2), dt(m, 2)), Tag(String::new()));
+        data[0] += &entry(200000, Category::Salary, Jan);
+        data[0] += &entry(-50000, Category::Home, Jan);
+        data[1] += &entry(-1250, Category::Food, Feb);
+        for name in &PlotKind::NAMES {
+            let kind = name.parse::<PlotKind>().unwrap();
+            assert_eq!(kind.keyword(), *name);
+            assert!(Plotter::from(&[]).svg(kind).is_none());
+            let svg = Plotter::from(&data).svg(kind).unwrap();
+            assert!(svg.starts_with("<svg"), "{:?}", kind);
+            assert!(!svg.contains("NaN"), "{:?}", kind);
+        }
+        let pie = Plotter::from(&data).svg(PlotKind::Pie).unwrap();
+        assert!(pie.contains("Home 98%") && pie.contains("Food 2%"));
+        // no expenses at all
+        assert!(Plotter::from(&data[..0]).svg(PlotKind::Pie).is_none());
+        let empty = vec![Summary::from_period(Between(dt(Jan, 1), dt(Jan, 31)))];
+        assert!(Plotter::from(&empty).svg(PlotKind::Pie).is_none());
+        assert!("donut".parse::<PlotKind>().is_err());
+    }
+
+    #[test]
+    fn captions() {
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let data = vec![Summary::from_period(Between(dt(Jan, 1), dt(Jan, 31)))];
+        let svg = Plotter::from(&data).with_title("Monthly").svg(PlotKind::Bars).unwrap();
+        assert!(svg.contains("Monthly") && svg.contains("Amount (€)") && svg.contains("Date"));
+        // one item per category, with the default colors
+        for i in 0..Category::COUNT {
+            let cat = Category::from_usize(i).unwrap();
+            assert!(svg.contains(cat.keyword()));
+            assert!(svg.contains(CategoryColors::colorblind().get(cat)));
+        }
+        let dollars = AmountFmt::default().with_symbol("$").with_prefix(true);
+        let svg = Plotter::from(&data).with_amount_fmt(dollars).svg(PlotKind::Bars).unwrap();
+        assert!(svg.contains("Amount ($)") && !svg.contains("Amount (€)"));
+        let colors = CategoryColors::classic().with(Category::Food, "#123456");
+        let svg = Plotter::from(&data).with_colors(colors).svg(PlotKind::Stacked).unwrap();
+        assert!(svg.contains("#123456") && svg.contains("cyan"));
+        assert!(!svg.contains(Category::Salary.keyword()));
+        let svg = Plotter::from(&data).svg(PlotKind::Balance).unwrap();
+        assert!(svg.contains("Balance"));
+        assert!(!svg.contains("bucket"));
+        let svg = Plotter::from(&data).with_tooltips(true).svg(PlotKind::Bars).unwrap();
+        assert_eq!(svg.matches("class=\"bucket\"").count(), 1);
+        assert!(svg.contains("2021-Jan-01 to 2021-Jan-31") && svg.contains("Total: 0.00€"));
+        // named as in .bil files
+        assert!(svg.contains("Mov: 0.00€") && !svg.contains("Movement"));
+        assert!(!Plotter::from(&data).with_tooltips(true).svg(PlotKind::Pie).is_some_and(|svg| svg.contains("bucket")));
+        // incomplete periods are faded out
+        use crate::util::{entry::Duration, summary::Calendar};
+        let cal = Calendar::from_aligned(Between(dt(Jan, 15), dt(Mar, 31)), Duration::Month, 1, Convention::default());
+        let svg = Plotter::from(cal.contents()).with_tooltips(true).svg(PlotKind::Bars).unwrap();
+        assert_eq!(svg.matches("class=\"partial\"").count(), 1);
+        assert!(svg.contains("2021-Jan-15 to 2021-Jan-31 (partial)"));
+        assert!(!Plotter::from(&data).svg(PlotKind::Bars).unwrap().contains("partial"));
+    }
+
+    #[test]
+    fn heatmap() {
+        use crate::util::entry::{Entry, Tag};
+        let dt = |y, m, d| Date::from(y, m, d).unwrap();
+        let mut data = vec![
+            Summary::from_period(Between(dt(2020, Dec, 31), dt(2020, Dec, 31))),
+            Summary::from_period(Between(dt(2021, Jan, 1), dt(2021, Jan, 1))),
+            Summary::from_period(Between(dt(2021, Jan, 4), dt(2021, Jan, 4))),
+        ];
+        let entry = |val, y, d| Entry::from(Amount(val), Category::Food, Between(dt(y, Jan, d), dt(y, Jan, d)), Tag(String::new()));
+        data[1] += &entry(-1250, 2021, 1);
+        data[2] += &entry(-300, 2021, 4);
+        let svg = Plotter::from(&data).with_tooltips(true).svg(PlotKind::Heatmap).unwrap();
+        // one block per year
+        assert!(svg.contains("\n2020\n</text>") && svg.contains("\n2021\n</text>"));
+        assert!(svg.contains("Fri 2021-Jan-01: -12.50€") && svg.contains("Thu 2020-Dec-31: 0.00€"));
+        // the day that spent the most is the reddest, empty days are grey
+        assert!(svg.contains("fill=\"#ff0000\"") && svg.contains(HeatmapDrawer::EMPTY));
+        assert!(svg.contains("-12.50€") && svg.contains("-3.00€") && svg.contains("Nothing"));
+        // Friday of the first week of 2021, Monday of the second one
+        let drawer = Plotter::from(&data).heatmap_plot();
+        let doc = drawer.render(&Caption { title: "", unit: "", legend: Vec::new(), tooltips: Vec::new(), partial: Vec::new() }).unwrap().to_string();
+        assert!(doc.contains(&format!("x=\"0\" y=\"{}\"", HeatmapDrawer::CELL * 10.0 + HeatmapDrawer::CELL * 4.0)));
+        assert!(doc.contains(&format!("x=\"{}\" y=\"{}\"", HeatmapDrawer::CELL, HeatmapDrawer::CELL * 10.0)));
+    }
+
+    #[test]
+    fn terminal() {
+        use crate::util::entry::{Entry, Tag};
+        assert!(Plotter::from(&[]).term(80, Palette::Plain).is_none());
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let mut data = vec![
+            Summary::from_period(Between(dt(Jan, 1), dt(Jan, 31))),
+            Summary::from_period(Between(dt(Feb, 1), dt(Feb, 28))),
+        ];
+        let entry = |val, cat, m| Entry::from(Amount(val), cat, Between(dt(m, 2), dt(m, 2)), Tag(String::new()));
+        data[0] += &entry(-5000, Category::Home, Jan);
+        data[1] += &entry(-2000, Category::Home, Feb);
+        data[1] += &entry(-3000, Category::Food, Feb);
+        let plot = Plotter::from(&data).with_title("Monthly").term(60, Palette::Plain).unwrap();
+        let lines = plot.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "Monthly");
+        assert!(lines.iter().all(|l| l.chars().count() <= 60));
+        // the highest graduation is on the top row
+        assert!(lines[1].contains("50.00€┤"));
+        // Home everywhere at the bottom, Food on top of it in February only
+        let bottom = lines[20];
+        assert!(bottom.contains("0.00€┤█") && bottom.ends_with('█'));
+        assert!(lines[5].contains("█%") && lines[15].ends_with('█') && !lines[15].contains('%'));
+        assert!(lines[23].starts_with("       █ Home  ▓ Pro  ▒ Clean"));
+        assert!(lines.iter().any(|l| l.contains("% Food")));
+        let colored = Plotter::from(&data).term(60, Palette::TrueColor).unwrap();
+        assert!(colored.contains(&CategoryColors::default().rgb(Category::Food).to_string()));
+        assert_eq!(CategoryColors::classic().rgb(Category::Home), Color(255, 0, 0));
+        assert_eq!(CategoryColors::default().with(Category::Home, "#0f8").rgb(Category::Home), Color(0, 255, 136));
+    }
+}
@@ -0,0 +1,846 @@
+//! Rasterization of the `.svg` plots into `.png` images
+//!
+//! Only the subset of SVG that `Plotter` produces is understood: paths made of
+//! straight lines and elliptical arcs, lines (possibly dashed), rectangles and text.
+//! Text is drawn with a built-in 5x7 bitmap font, so that neither a system library
+//! nor a font file is needed. Shapes are anti-aliased with 4 samples per row of pixels.
+
+use svg::node::element::path::{Command, Data, Position};
+use svg::node::element::tag::Type;
+use svg::node::Attributes;
+use svg::parser::Event;
+
+use crate::cli::{png, table::Color};
+
+/// Resolution at which one unit of the `viewBox` is one pixel
+pub const BASE_DPI: f64 = 96.0;
+
+/// Largest image that `png` draws, about 300MB of pixels
+pub const MAX_PIXELS: usize = 100_000_000;
+
+/// Size of an image that `png` refuses to draw because it exceeds `MAX_PIXELS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLarge {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{} pixels is more than the limit of {} pixels", self.width, self.height, MAX_PIXELS)
+    }
+}
+
+type Point = (f64, f64);
+
+/// Color of the SVG notation `text`: `#rgb`, `#rrggbb`, `rgb(r, g, b)` or a named color
+pub fn color(text: &str) -> Option<Color> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix('#') {
+        let digits = hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<Vec<_>>>()?;
+        return match digits[..] {
+            [r, g, b] => Some(Color(r * 17, g * 17, b * 17)),
+            [r1, r0, g1, g0, b1, b0] => Some(Color(r1 * 16 + r0, g1 * 16 + g0, b1 * 16 + b0)),
+            _ => None,
+        };
+    }
+    let lower = text.to_ascii_lowercase();
+    if let Some(args) = lower.strip_prefix("rgb(").and_then(|s| s.strip_suffix(')')) {
+        // integers from 0 to 255 or percentages, out of range values are clamped
+        let channel = |s: &str| {
+            let s = s.trim();
+            let value = match s.strip_suffix('%') {
+                Some(percent) => percent.trim().parse::<f64>().ok()? * 255.0 / 100.0,
+                None => s.parse::<i64>().ok()? as f64,
+            };
+            Some(value.round().clamp(0.0, 255.0) as u8)
+        };
+        let channels = args.split(',').map(channel).collect::<Option<Vec<_>>>()?;
+        return match channels[..] {
+            [r, g, b] => Some(Color(r, g, b)),
+            _ => None,
+        };
+    }
+    NAMED
+        .binary_search_by(|(name, _)| name.cmp(&lower.as_str()))
+        .ok()
+        .map(|i| NAMED[i].1)
+}
+
+/// Named colors of SVG and CSS, in alphabetical order
+const NAMED: &[(&str, Color)] = &[
+    ("aliceblue", Color(240, 248, 255)),
+    ("antiquewhite", Color(250, 235, 215)),
+    ("aqua", Color(0, 255, 255)),
+    ("aquamarine", Color(127, 255, 212)),
+    ("azure", Color(240, 255, 255)),
+    ("beige", Color(245, 245, 220)),
+    ("bisque", Color(255, 228, 196)),
+    ("black", Color(0, 0, 0)),
+    ("blanchedalmond", Color(255, 235, 205)),
+    ("blue", Color(0, 0, 255)),
+    ("blueviolet", Color(138, 43, 226)),
+    ("brown", Color(165, 42, 42)),
+    ("burlywood", Color(222, 184, 135)),
+    ("cadetblue", Color(95, 158, 160)),
+    ("chartreuse", Color(127, 255, 0)),
+    ("chocolate", Color(210, 105, 30)),
+    ("coral", Color(255, 127, 80)),
+    ("cornflowerblue", Color(100, 149, 237)),
+    ("cornsilk", Color(255, 248, 220)),
+    ("crimson", Color(220, 20, 60)),
+    ("cyan", Color(0, 255, 255)),
+    ("darkblue", Color(0, 0, 139)),
+    ("darkcyan", Color(0, 139, 139)),
+    ("darkgoldenrod", Color(184, 134, 11)),
+    ("darkgray", Color(169, 169, 169)),
+    ("darkgreen", Color(0, 100, 0)),
+    ("darkgrey", Color(169, 169, 169)),
+    ("darkkhaki", Color(189, 183, 107)),
+    ("darkmagenta", Color(139, 0, 139)),
+    ("darkolivegreen", Color(85, 107, 47)),
+    ("darkorange", Color(255, 140, 0)),
+    ("darkorchid", Color(153, 50, 204)),
+    ("darkred", Color(139, 0, 0)),
+    ("darksalmon", Color(233, 150, 122)),
+    ("darkseagreen", Color(143, 188, 143)),
+    ("darkslateblue", Color(72, 61, 139)),
+    ("darkslategray", Color(47, 79, 79)),
+    ("darkslategrey", Color(47, 79, 79)),
+    ("darkturquoise", Color(0, 206, 209)),
+    ("darkviolet", Color(148, 0, 211)),
+    ("deeppink", Color(255, 20, 147)),
+    ("deepskyblue", Color(0, 191, 255)),
+    ("dimgray", Color(105, 105, 105)),
+    ("dimgrey", Color(105, 105, 105)),
+    ("dodgerblue", Color(30, 144, 255)),
+    ("firebrick", Color(178, 34, 34)),
+    ("floralwhite", Color(255, 250, 240)),
+    ("forestgreen", Color(34, 139, 34)),
+    ("fuchsia", Color(255, 0, 255)),
+    ("gainsboro", Color(220, 220, 220)),
+    ("ghostwhite", Color(248, 248, 255)),
+    ("gold", Color(255, 215, 0)),
+    ("goldenrod", Color(218, 165, 32)),
+    ("gray", Color(128, 128, 128)),
+    ("green", Color(0, 128, 0)),
+    ("greenyellow", Color(173, 255, 47)),
+    ("grey", Color(128, 128, 128)),
+    ("honeydew", Color(240, 255, 240)),
+    ("hotpink", Color(255, 105, 180)),
+    ("indianred", Color(205, 92, 92)),
+    ("indigo", Color(75, 0, 130)),
+    ("ivory", Color(255, 255, 240)),
+    ("khaki", Color(240, 230, 140)),
+    ("lavender", Color(230, 230, 250)),
+    ("lavenderblush", Color(255, 240, 245)),
+    ("lawngreen", Color(124, 252, 0)),
+    ("lemonchiffon", Color(255, 250, 205)),
+    ("lightblue", Color(173, 216, 230)),
+    ("lightcoral", Color(240, 128, 128)),
+    ("lightcyan", Color(224, 255, 255)),
+    ("lightgoldenrodyellow", Color(250, 250, 210)),
+    ("lightgray", Color(211, 211, 211)),
+    ("lightgreen", Color(144, 238, 144)),
+    ("lightgrey", Color(211, 211, 211)),
+    ("lightpink", Color(255, 182, 193)),
+    ("lightsalmon", Color(255, 160, 122)),
+    ("lightseagreen", Color(32, 178, 170)),
+    ("lightskyblue", Color(135, 206, 250)),
+    ("lightslategray", Color(119, 136, 153)),
+    ("lightslategrey", Color(119, 136, 153)),
+    ("lightsteelblue", Color(176, 196, 222)),
+    ("lightyellow", Color(255, 255, 224)),
+    ("lime", Color(0, 255, 0)),
+    ("limegreen", Color(50, 205, 50)),
+    ("linen", Color(250, 240, 230)),
+    ("magenta", Color(255, 0, 255)),
+    ("maroon", Color(128, 0, 0)),
+    ("mediumaquamarine", Color(102, 205, 170)),
+    ("mediumblue", Color(0, 0, 205)),
+    ("mediumorchid", Color(186, 85, 211)),
+    ("mediumpurple", Color(147, 112, 219)),
+    ("mediumseagreen", Color(60, 179, 113)),
+    ("mediumslateblue", Color(123, 104, 238)),
+    ("mediumspringgreen", Color(0, 250, 154)),
+    ("mediumturquoise", Color(72, 209, 204)),
+    ("mediumvioletred", Color(199, 21, 133)),
+    ("midnightblue", Color(25, 25, 112)),
+    ("mintcream", Color(245, 255, 250)),
+    ("mistyrose", Color(255, 228, 225)),
+    ("moccasin", Color(255, 228, 181)),
+    ("navajowhite", Color(255, 222, 173)),
+    ("navy", Color(0, 0, 128)),
+    ("oldlace", Color(253, 245, 230)),
+    ("olive", Color(128, 128, 0)),
+    ("olivedrab", Color(107, 142, 35)),
+    ("orange", Color(255, 165, 0)),
+    ("orangered", Color(255, 69, 0)),
+    ("orchid", Color(218, 112, 214)),
+    ("palegoldenrod", Color(238, 232, 170)),
+    ("palegreen", Color(152, 251, 152)),
+    ("paleturquoise", Color(175, 238, 238)),
+    ("palevioletred", Color(219, 112, 147)),
+    ("papayawhip", Color(255, 239, 213)),
+    ("peachpuff", Color(255, 218, 185)),
+    ("peru", Color(205, 133, 63)),
+    ("pink", Color(255, 192, 203)),
+    ("plum", Color(221, 160, 221)),
+    ("powderblue", Color(176, 224, 230)),
+    ("purple", Color(128, 0, 128)),
+    ("rebeccapurple", Color(102, 51, 153)),
+    ("red", Color(255, 0, 0)),
+    ("rosybrown", Color(188, 143, 143)),
+    ("royalblue", Color(65, 105, 225)),
+    ("saddlebrown", Color(139, 69, 19)),
+    ("salmon", Color(250, 128, 114)),
+    ("sandybrown", Color(244, 164, 96)),
+    ("seagreen", Color(46, 139, 87)),
+    ("seashell", Color(255, 245, 238)),
+    ("sienna", Color(160, 82, 45)),
+    ("silver", Color(192, 192, 192)),
+    ("skyblue", Color(135, 206, 235)),
+    ("slateblue", Color(106, 90, 205)),
+    ("slategray", Color(112, 128, 144)),
+    ("slategrey", Color(112, 128, 144)),
+    ("snow", Color(255, 250, 250)),
+    ("springgreen", Color(0, 255, 127)),
+    ("steelblue", Color(70, 130, 180)),
+    ("tan", Color(210, 180, 140)),
+    ("teal", Color(0, 128, 128)),
+    ("thistle", Color(216, 191, 216)),
+    ("tomato", Color(255, 99, 71)),
+    ("turquoise", Color(64, 224, 208)),
+    ("violet", Color(238, 130, 238)),
+    ("wheat", Color(245, 222, 179)),
+    ("white", Color(255, 255, 255)),
+    ("whitesmoke", Color(245, 245, 245)),
+    ("yellow", Color(255, 255, 0)),
+    ("yellowgreen", Color(154, 205, 50)),
+];
+
+/// Render `svg` at `dpi` and encode it as PNG, on a white background
+pub fn png(svg: &str, dpi: f64) -> Result<Vec<u8>, TooLarge> {
+    let mut canvas: Option<Canvas> = None;
+    // attributes of the `<text>` being read, `None` inside of a `<title>`
+    let mut text: Option<Attributes> = None;
+    let mut in_title = false;
+    for event in svg::read(svg).into_iter().flatten() {
+        match event {
+            Event::Tag("svg", Type::Start, attrs) => {
+                let view = number_list(attrs.get("viewBox").map(|v| &v[..]).unwrap_or("0 0 100 100"));
+                let (x, y, w, h) = match view[..] {
+                    [x, y, w, h] => (x, y, w, h),
+                    _ => (0.0, 0.0, 100.0, 100.0),
+                };
+                let scale = dpi / BASE_DPI;
+                let (width, height) = ((w * scale).ceil() as usize, (h * scale).ceil() as usize);
+                if width.checked_mul(height).is_none_or(|pixels| pixels > MAX_PIXELS) {
+                    return Err(TooLarge { width, height });
+                }
+                canvas = Some(Canvas::new(width, height, Transform::translate(-x, -y).then(Transform::scale(scale))));
+            }
+            Event::Tag("title", Type::Start, _) => in_title = true,
+            Event::Tag("title", Type::End, _) => in_title = false,
+            Event::Tag("text", Type::Start, attrs) => text = Some(attrs),
+            Event::Tag("text", Type::End, _) => text = None,
+            Event::Text(content) if !in_title => {
+                if let (Some(canvas), Some(attrs)) = (canvas.as_mut(), text.as_ref()) {
+                    canvas.text(attrs, content.trim());
+                }
+            }
+            Event::Tag(name, Type::Start, attrs) | Event::Tag(name, Type::Empty, attrs) => {
+                if let Some(canvas) = canvas.as_mut() {
+                    match name {
+                        "path" => canvas.path(&attrs),
+                        "line" => canvas.line(&attrs),
+                        "rect" => canvas.rect(&attrs),
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    let canvas = canvas.unwrap_or_else(|| Canvas::new(1, 1, Transform::scale(1.0)));
+    Ok(png::encode(canvas.width, canvas.height, &canvas.pixels))
+}
+
+fn number_list(text: &str) -> Vec<f64> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| s.parse().ok())
+        .collect()
+}
+
+fn number(attrs: &Attributes, name: &str) -> f64 {
+    attrs.get(name).and_then(|v| v.trim().parse().ok()).unwrap_or(0.0)
+}
+
+/// `(a x + c y + e, b x + d y + f)` for `[a, b, c, d, e, f]`
+#[derive(Debug, Clone, Copy)]
+struct Transform([f64; 6]);
+
+impl Transform {
+    fn translate(x: f64, y: f64) -> Self {
+        Self([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    fn scale(s: f64) -> Self {
+        Self([s, 0.0, 0.0, s, 0.0, 0.0])
+    }
+
+    /// Rotation by `deg` degrees around `(cx, cy)`
+    fn rotate(deg: f64, cx: f64, cy: f64) -> Self {
+        let (sin, cos) = deg.to_radians().sin_cos();
+        Self::translate(-cx, -cy)
+            .then(Self([cos, sin, -sin, cos, 0.0, 0.0]))
+            .then(Self::translate(cx, cy))
+    }
+
+    /// Apply `self`, then `other`
+    fn then(self, other: Self) -> Self {
+        let [a, b, c, d, e, f] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = other.0;
+        Self([
+            a2 * a + c2 * b,
+            b2 * a + d2 * b,
+            a2 * c + c2 * d,
+            b2 * c + d2 * d,
+            a2 * e + c2 * f + e2,
+            b2 * e + d2 * f + f2,
+        ])
+    }
+
+    fn apply(&self, (x, y): Point) -> Point {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    /// Scale factor of lengths, transformations here are similarities
+    fn ratio(&self) -> f64 {
+        (self.0[0] * self.0[3] - self.0[1] * self.0[2]).abs().sqrt()
+    }
+
+    /// `transform` attribute, made of `translate` and `rotate` functions
+    fn parse(text: &str) -> Self {
+        let mut transform = Self::translate(0.0, 0.0);
+        for function in text.split(')') {
+            let (name, args) = match function.split_once('(') {
+                Some((name, args)) => (name.trim(), number_list(args)),
+                None => continue,
+            };
+            let inner = match (name, &args[..]) {
+                ("translate", [x, y]) => Self::translate(*x, *y),
+                ("translate", [x]) => Self::translate(*x, 0.0),
+                ("rotate", [deg]) => Self::rotate(*deg, 0.0, 0.0),
+                ("rotate", [deg, cx, cy]) => Self::rotate(*deg, *cx, *cy),
+                _ => continue,
+            };
+            // functions apply from the rightmost one
+            transform = inner.then(transform);
+        }
+        transform
+    }
+}
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 3]>,
+    /// from user units to pixels
+    view: Transform,
+}
+
+impl Canvas {
+    /// Sub-rows sampled in each row of pixels
+    const SAMPLES: usize = 4;
+
+    fn new(width: usize, height: usize, view: Transform) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[255; 3]; width * height],
+            view,
+        }
+    }
+
+    fn paint(attrs: &Attributes, name: &str, default: Option<Color>) -> Option<Color> {
+        match attrs.get(name) {
+            Some(value) => color(value),
+            None => default,
+        }
+    }
+
+    fn path(&mut self, attrs: &Attributes) {
+        let subpaths = match attrs.get("d").and_then(|d| Data::parse(d).ok()) {
+            Some(data) => flatten(&data),
+            None => return,
+        };
+        let subpaths = subpaths
+            .iter()
+            .map(|points| points.iter().map(|p| self.view.apply(*p)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        if let Some(fill) = Self::paint(attrs, "fill", Some(Color(0, 0, 0))) {
+            let opacity = attrs.get("fill-opacity").and_then(|v| v.parse().ok()).unwrap_or(1.0);
+            self.fill(&subpaths, fill, opacity);
+        }
+        if let Some(stroke) = Self::paint(attrs, "stroke", None) {
+            let width = attrs.get("stroke-width").and_then(|v| v.parse().ok()).unwrap_or(1.0) * self.view.ratio();
+            for points in &subpaths {
+                self.stroke(points, width, stroke);
+            }
+        }
+    }
+
+    fn line(&mut self, attrs: &Attributes) {
+        let stroke = match Self::paint(attrs, "stroke", None) {
+            Some(stroke) => stroke,
+            None => return,
+        };
+        let start = (number(attrs, "x1"), number(attrs, "y1"));
+        let end = (number(attrs, "x2"), number(attrs, "y2"));
+        let width = attrs.get("stroke-width").and_then(|v| v.parse().ok()).unwrap_or(1.0) * self.view.ratio();
+        let dashes = attrs.get("stroke-dasharray").map(|v| number_list(v)).unwrap_or_default();
+        let offset = number(attrs, "stroke-dashoffset");
+        for (a, b) in dash(start, end, &dashes, offset) {
+            self.stroke(&[self.view.apply(a), self.view.apply(b)], width, stroke);
+        }
+    }
+
+    fn rect(&mut self, attrs: &Attributes) {
+        let (x, y) = (number(attrs, "x"), number(attrs, "y"));
+        let (w, h) = (number(attrs, "width"), number(attrs, "height"));
+        let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)]
+            .iter()
+            .map(|p| self.view.apply(*p))
+            .collect::<Vec<_>>();
+        if let Some(fill) = Self::paint(attrs, "fill", Some(Color(0, 0, 0))) {
+            let opacity = attrs.get("fill-opacity").and_then(|v| v.parse().ok()).unwrap_or(1.0);
+            self.fill(std::slice::from_ref(&corners), fill, opacity);
+        }
+        if let Some(stroke) = Self::paint(attrs, "stroke", None) {
+            let mut outline = corners;
+            outline.push(outline[0]);
+            self.stroke(&outline, self.view.ratio(), stroke);
+        }
+    }
+
+    fn text(&mut self, attrs: &Attributes, content: &str) {
+        let size = attrs.get("font-size").and_then(|v| v.parse().ok()).unwrap_or(16.0);
+        // the outline of stroked text makes it look bold
+        let bold = attrs.get("font-weight").map(|v| &v[..]) == Some("bold") || attrs.get("stroke").is_some();
+        let fill = Self::paint(attrs, "fill", None)
+            .or_else(|| Self::paint(attrs, "stroke", None))
+            .unwrap_or(Color(0, 0, 0));
+        // one pixel of the font, glyphs are 5x7 in a cell of 6x8: the height of capitals
+        // and the average advance are then close to those of usual sans-serif fonts
+        let px = size / 10.0;
+        let length = content.chars().count() as f64 * 6.0 * px - px;
+        let shift = match attrs.get("text-anchor").map(|v| &v[..]) {
+            Some("middle") => length / 2.0,
+            Some("end") => length,
+            _ => 0.0,
+        };
+        let transform = Transform::parse(attrs.get("transform").map(|v| &v[..]).unwrap_or(""));
+        let transform = transform.then(self.view);
+        let (left, top) = (number(attrs, "x") - shift, number(attrs, "y") - 7.0 * px);
+        let thickness = if bold { px * 1.4 } else { px };
+        let mut squares = Vec::new();
+        for (i, c) in content.chars().enumerate() {
+            for (col, bits) in glyph(c).iter().enumerate() {
+                for row in 0..7 {
+                    if bits >> row & 1 == 1 {
+                        let x = left + (i * 6 + col) as f64 * px;
+                        let y = top + row as f64 * px;
+                        let square = [(x, y), (x + thickness, y), (x + thickness, y + px), (x, y + px)];
+                        squares.push(square.iter().map(|p| transform.apply(*p)).collect::<Vec<_>>());
+                    }
+                }
+            }
+        }
+        self.fill(&squares, fill, 1.0);
+    }
+
+    /// Thick line through `points`, in pixels
+    fn stroke(&mut self, points: &[Point], width: f64, color: Color) {
+        let half = width / 2.0;
+        let mut shapes = Vec::new();
+        for pair in points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+            if length == 0.0 {
+                continue;
+            }
+            let (nx, ny) = (-(y2 - y1) / length * half, (x2 - x1) / length * half);
+            shapes.push(vec![(x1 + nx, y1 + ny), (x2 + nx, y2 + ny), (x2 - nx, y2 - ny), (x1 - nx, y1 - ny)]);
+        }
+        // round joins between segments
+        for (x, y) in points.iter().skip(1).take(points.len().saturating_sub(2)) {
+            shapes.push(
+                (0..12)
+                    .map(|k| {
+                        let angle = k as f64 * std::f64::consts::TAU / 12.0;
+                        (x + half * angle.cos(), y + half * angle.sin())
+                    })
+                    .collect(),
+            );
+        }
+        // shapes are filled one by one, overlapping shapes with opposite
+        // orientations would cancel each other out
+        for shape in shapes {
+            self.fill(&[shape], color, 1.0);
+        }
+    }
+
+    /// Fill `polygons` in pixels with the nonzero rule
+    fn fill(&mut self, polygons: &[Vec<Point>], color: Color, opacity: f64) {
+        if opacity <= 0.0 {
+            return;
+        }
+        let edges = polygons
+            .iter()
+            .flat_map(|poly| (0..poly.len()).map(move |i| (poly[i], poly[(i + 1) % poly.len()])))
+            .filter(|(a, b)| a.1 != b.1)
+            .collect::<Vec<_>>();
+        let bounds = edges.iter().flat_map(|(a, b)| [*a, *b]);
+        let (xmin, xmax, ymin, ymax) = bounds.fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+            |(x0, x1, y0, y1), (x, y)| (x0.min(x), x1.max(x), y0.min(y), y1.max(y)),
+        );
+        if edges.is_empty() || xmax < 0.0 || ymax < 0.0 {
+            return;
+        }
+        let xmin = xmin.max(0.0).floor() as usize;
+        let xmax = (xmax.ceil() as usize).min(self.width);
+        let ymin = ymin.max(0.0).floor() as usize;
+        let ymax = (ymax.ceil() as usize).min(self.height);
+        if xmin >= xmax {
+            return;
+        }
+        let mut coverage = vec![0.0; xmax - xmin];
+        for row in ymin..ymax {
+            coverage.iter_mut().for_each(|c| *c = 0.0);
+            for k in 0..Self::SAMPLES {
+                let y = row as f64 + (k as f64 + 0.5) / Self::SAMPLES as f64;
+                let mut crossings = edges
+                    .iter()
+                    .filter_map(|(a, b)| {
+                        let (lo, hi, dir) = if a.1 < b.1 { (a, b, 1) } else { (b, a, -1) };
+                        (lo.1 <= y && y < hi.1).then(|| (lo.0 + (y - lo.1) / (hi.1 - lo.1) * (hi.0 - lo.0), dir))
+                    })
+                    .collect::<Vec<_>>();
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let mut winding = 0;
+                let mut start = 0.0;
+                for (x, dir) in crossings {
+                    let before = winding;
+                    winding += dir;
+                    if before == 0 && winding != 0 {
+                        start = x;
+                    } else if before != 0 && winding == 0 {
+                        // horizontal coverage of the span is exact
+                        let (a, b) = (start.max(xmin as f64), x.min(xmax as f64));
+                        let mut px = a.floor().max(xmin as f64);
+                        while px < b {
+                            let overlap = b.min(px + 1.0) - a.max(px);
+                            coverage[px as usize - xmin] += overlap / Self::SAMPLES as f64;
+                            px += 1.0;
+                        }
+                    }
+                }
+            }
+            for (i, c) in coverage.iter().enumerate() {
+                let alpha = c.min(1.0) * opacity;
+                if alpha > 0.0 {
+                    let pixel = &mut self.pixels[row * self.width + xmin + i];
+                    let new = [color.0, color.1, color.2];
+                    for ch in 0..3 {
+                        pixel[ch] = (pixel[ch] as f64 * (1.0 - alpha) + new[ch] as f64 * alpha).round() as u8;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sections of the line between `start` and `end` that are drawn
+fn dash(start: Point, end: Point, dashes: &[f64], offset: f64) -> Vec<(Point, Point)> {
+    let period = dashes.iter().sum::<f64>();
+    if dashes.is_empty() || period <= 0.0 {
+        return vec![(start, end)];
+    }
+    let length = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt();
+    let at = |d: f64| {
+        let t = (d / length).clamp(0.0, 1.0);
+        (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t)
+    };
+    let mut sections = Vec::new();
+    // position along the line of the start of the current dash pattern
+    let mut pos = -offset.rem_euclid(period);
+    while pos < length {
+        let mut d = pos;
+        for (i, len) in dashes.iter().enumerate() {
+            // odd items are gaps
+            if i % 2 == 0 && d + len > 0.0 && d < length {
+                sections.push((at(d.max(0.0)), at((d + len).min(length))));
+            }
+            d += len;
+        }
+        pos += period;
+    }
+    sections
+}
+
+/// Subpaths of `data` as polygons, arcs are approximated by straight segments
+fn flatten(data: &Data) -> Vec<Vec<Point>> {
+    let mut subpaths: Vec<Vec<Point>> = Vec::new();
+    let mut current = (0.0, 0.0);
+    let mut first = (0.0, 0.0);
+    for command in data.iter() {
+        let (position, params, step) = match command {
+            Command::Move(pos, params) | Command::Line(pos, params) => (pos, params, 2),
+            Command::HorizontalLine(pos, params) | Command::VerticalLine(pos, params) => (pos, params, 1),
+            Command::EllipticalArc(pos, params) => (pos, params, 7),
+            Command::Close => {
+                current = first;
+                continue;
+            }
+            _ => continue,
+        };
+        let relative = matches!(position, Position::Relative);
+        for (i, args) in params.chunks_exact(step).enumerate() {
+            let args = args.iter().map(|a| *a as f64).collect::<Vec<_>>();
+            let base = if relative { current } else { (0.0, 0.0) };
+            let target = match command {
+                Command::HorizontalLine(..) => (base.0 + args[0], current.1),
+                Command::VerticalLine(..) => (current.0, base.1 + args[0]),
+                Command::EllipticalArc(..) => (base.0 + args[5], base.1 + args[6]),
+                _ => (base.0 + args[0], base.1 + args[1]),
+            };
+            match command {
+                // pairs after the first one of a move are lines
+                Command::Move(..) if i == 0 => {
+                    subpaths.push(vec![target]);
+                    first = target;
+                }
+                Command::EllipticalArc(..) => {
+                    let points = arc(current, args[0], args[1], args[2], args[3] != 0.0, args[4] != 0.0, target);
+                    if let Some(path) = subpaths.last_mut() {
+                        path.extend(points);
+                    }
+                }
+                _ => {
+                    if let Some(path) = subpaths.last_mut() {
+                        path.push(target);
+                    }
+                }
+            }
+            current = target;
+        }
+    }
+    subpaths
+}
+
+/// Points of an elliptical arc, excluding its start,
+/// following the conversion to center parameterization of the SVG specification
+fn arc(start: Point, rx: f64, ry: f64, rotation: f64, large: bool, sweep: bool, end: Point) -> Vec<Point> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 || start == end {
+        return vec![end];
+    }
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((start.0 - end.0) / 2.0, (start.1 - end.1) / 2.0);
+    let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+    let lambda = x1 * x1 / (rx * rx) + y1 * y1 / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large == sweep { -1.0 } else { 1.0 };
+    let coef = sign * (num / den).max(0.0).sqrt();
+    let (cx1, cy1) = (coef * rx * y1 / ry, -coef * ry * x1 / rx);
+    let (cx, cy) = (
+        cos * cx1 - sin * cy1 + (start.0 + end.0) / 2.0,
+        sin * cx1 + cos * cy1 + (start.1 + end.1) / 2.0,
+    );
+    let angle = |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let theta = angle(1.0, 0.0, (x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle((x1 - cx1) / rx, (y1 - cy1) / ry, (-x1 - cx1) / rx, (-y1 - cy1) / ry);
+    if !sweep && delta > 0.0 {
+        delta -= std::f64::consts::TAU;
+    } else if sweep && delta < 0.0 {
+        delta += std::f64::consts::TAU;
+    }
+    let steps = ((delta.abs() / (std::f64::consts::PI / 64.0)).ceil() as usize).max(1);
+    (1..=steps)
+        .map(|k| {
+            let t = theta + delta * k as f64 / steps as f64;
+            let (st, ct) = t.sin_cos();
+            (cos * rx * ct - sin * ry * st + cx, sin * rx * ct + cos * ry * st + cy)
+        })
+        .collect()
+}
+
+/// Columns of the glyph of `c`, the least significant bit is the top row
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '€' => [0x14, 0x3e, 0x55, 0x55, 0x41],
+        ' '..='~' => FONT[c as usize - ' ' as usize],
+        _ => FONT['?' as usize - ' ' as usize],
+    }
+}
+
+/// Printable ASCII characters
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // )
+    [0x14, 0x08, 0x3e, 0x08, 0x14], // *
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // 0
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // D
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7f, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3e, 0x41, 0x49, 0x49, 0x7a], // G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // H
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // J
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f], // M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // N
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // T
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // V
+    [0x3f, 0x40, 0x38, 0x40, 0x3f], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7f, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7f, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // f
+    [0x0c, 0x52, 0x52, 0x52, 0x3e], // g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // j
+    [0x7f, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // l
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // t
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(color("#0072B2"), Some(Color(0, 114, 178)));
+        assert_eq!(color("#f80"), Some(Color(255, 136, 0)));
+        assert_eq!(color("grey"), Some(Color(128, 128, 128)));
+        assert_eq!(color("teal"), Some(Color(0, 128, 128)));
+        assert_eq!(color("NavajoWhite"), Some(Color(255, 222, 173)));
+        assert_eq!(color("rgb(0, 114, 178)"), Some(Color(0, 114, 178)));
+        assert_eq!(color("rgb(100%,50%,0%)"), Some(Color(255, 128, 0)));
+        assert_eq!(color("none"), None);
+        assert_eq!(color("light blue"), None);
+        assert_eq!(color("rgb(1, 2)"), None);
+        assert_eq!(color("#12345"), None);
+        assert!(NAMED.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn shapes() {
+        let quarter = arc((1.0, 0.0), 1.0, 1.0, 0.0, false, true, (0.0, 1.0));
+        let (x, y) = quarter[quarter.len() / 2 - 1];
+        assert!((x - 0.5f64.sqrt()).abs() < 1e-6 && (y - 0.5f64.sqrt()).abs() < 1e-6);
+        let data = Data::parse("M0,0 L10,0 L10,10 z M20,20 l5,0").unwrap();
+        assert_eq!(flatten(&data), vec![vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], vec![(20.0, 20.0), (25.0, 20.0)]]);
+        assert_eq!(dash((0.0, 0.0), (50.0, 0.0), &[15.0, 20.0], 15.0), vec![((20.0, 0.0), (35.0, 0.0))]);
+        let t = Transform::parse("rotate(90, 1, 1) translate(1 0)");
+        let (x, y) = t.apply((0.0, 0.0));
+        assert!((x - 2.0).abs() < 1e-9 && (y - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pixels() {
+        let svg = r##"<svg viewBox="-10 -10 20 20"><rect x="-10" y="-10" width="10" height="20" fill="#ff0000"/></svg>"##;
+        let mut canvas = Canvas::new(20, 20, Transform::translate(10.0, 10.0));
+        canvas.rect(&[("x", "-10"), ("y", "-10"), ("width", "10"), ("height", "20"), ("fill", "#ff0000")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), svg::node::Value::from(*v)))
+            .collect());
+        assert_eq!(canvas.pixels[0], [255, 0, 0]);
+        assert_eq!(canvas.pixels[9], [255, 0, 0]);
+        assert_eq!(canvas.pixels[10], [255, 255, 255]);
+        // twice the resolution
+        let png = png(svg, 2.0 * BASE_DPI).unwrap();
+        assert_eq!(&png[16..24], &[0, 0, 0, 40, 0, 0, 0, 40]);
+        // refused before anything is allocated
+        assert_eq!(super::png(svg, 1e6), Err(TooLarge { width: 208334, height: 208334 }));
+        assert!(super::png(svg, 4e9).is_err());
+    }
+}
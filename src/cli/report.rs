@@ -0,0 +1,152 @@
+//! Single-page HTML report: tables, plots and the largest entries
+//!
+//! The page needs no external asset: plots are inline `<svg>` elements whose
+//! tooltips are `<title>` elements, and the list of entries is sorted by a short
+//! inline script when clicking on a header.
+
+use std::fmt;
+
+use crate::cli::table::{escape, html_document};
+use crate::export;
+use crate::util::{currency::AmountFmt, entry::Entry};
+
+pub struct HtmlReport {
+    title: String,
+    /// heading and contents
+    sections: Vec<(String, String)>,
+    entries: Vec<Entry>,
+    amount_fmt: AmountFmt,
+}
+
+impl HtmlReport {
+    pub fn new<S>(title: S) -> Self
+    where
+        S: ToString,
+    {
+        Self {
+            title: title.to_string(),
+            sections: Vec::new(),
+            entries: Vec::new(),
+            amount_fmt: AmountFmt::default(),
+        }
+    }
+
+    /// Format of the amounts of the entries
+    pub fn with_amount_fmt(mut self, fmt: AmountFmt) -> Self {
+        self.amount_fmt = fmt;
+        self
+    }
+
+    /// Add a heading followed by `html`, which is not escaped
+    pub fn with_section<S>(mut self, heading: S, html: String) -> Self
+    where
+        S: ToString,
+    {
+        self.sections.push((heading.to_string(), html));
+        self
+    }
+
+    /// List the `count` entries of largest absolute value
+    pub fn with_entries(mut self, entries: &[Entry], count: usize) -> Self {
+        let mut entries = entries.to_vec();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.value().0.abs()));
+        entries.truncate(count);
+        self.entries = entries;
+        self
+    }
+
+    fn entries_table(&self) -> String {
+        let mut text = String::from("<table class=\"entries\">\n  <thead><tr>");
+        for (header, class) in &[
+            ("Start", ""),
+            ("End", ""),
+            ("Category", ""),
+            ("Amount", " class=\"amount\""),
+            ("Tag", ""),
+        ] {
+            text.push_str(&format!("<th data-sort=\"\"{}>{}</th>", class, header));
+        }
+        text.push_str("</tr></thead>\n  <tbody>\n");
+        for entry in &self.entries {
+            let (start, end) = (export::iso_date(entry.period().0), export::iso_date(entry.period().1));
+            let category = entry.category().keyword();
+            let tag = escape(&entry.tag().map(|t| t.to_string()).unwrap_or_default());
+            text.push_str(&format!(
+                "  <tr><td data-value=\"{s}\">{s}</td><td data-value=\"{e}\">{e}</td>\
+                 <td data-value=\"{c}\">{c}</td><td class=\"amount\" data-value=\"{v}\">{a}</td>\
+                 <td data-value=\"{t}\">{t}</td></tr>\n",
+                s = start,
+                e = end,
+                c = category,
+                v = entry.value().0,
+                a = escape(&self.amount_fmt.format(entry.value())),
+                t = tag,
+            ));
+        }
+        text.push_str("  </tbody>\n</table>\n");
+        text
+    }
+}
+
+/// Sort the rows of a table by the `data-value` of the clicked column,
+/// numerically if both values are numbers
+const SORT_SCRIPT: &str = r#"<script>
+document.querySelectorAll("th[data-sort]").forEach(function (th) {
+  th.addEventListener("click", function () {
+    var body = th.closest("table").tBodies[0];
+    var col = th.cellIndex;
+    var asc = th.dataset.sort !== "asc";
+    th.closest("tr").querySelectorAll("th").forEach(function (h) { h.dataset.sort = ""; });
+    th.dataset.sort = asc ? "asc" : "desc";
+    var rows = Array.from(body.rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[col].dataset.value, y = b.cells[col].dataset.value;
+      var d = (isNaN(x) || isNaN(y) || x === "" || y === "") ? x.localeCompare(y) : x - y;
+      return asc ? d : -d;
+    });
+    rows.forEach(function (r) { body.appendChild(r); });
+  });
+});
+</script>
+"#;
+
+impl fmt::Display for HtmlReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut body = format!("<h1>{}</h1>\n", escape(&self.title));
+        for (heading, html) in &self.sections {
+            body.push_str(&format!("<h2>{}</h2>\n{}\n", escape(heading), html));
+        }
+        if !self.entries.is_empty() {
+            body.push_str("<h2>Largest entries</h2>\n");
+            body.push_str(&self.entries_table());
+            body.push_str(SORT_SCRIPT);
+        }
+        write!(f, "{}", html_document(&self.title, &body))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::{
+        date::{Between, Date, Month::*},
+        entry::{Amount, Category, Tag},
+    };
+
+    #[test]
+    fn largest_entries() {
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let entry = |val, tag: &str| Entry::from(Amount(val), Category::Food, Between(dt(Jan, 2), dt(Jan, 3)), Tag(tag.to_string()));
+        let entries = vec![entry(-100, "bread"), entry(-5000, "<feast>"), entry(2000, "refund")];
+        let page = HtmlReport::new("Review")
+            .with_section("Monthly", String::from("<svg></svg>"))
+            .with_entries(&entries, 2)
+            .to_string();
+        assert!(page.contains("<h1>Review</h1>") && page.contains("<h2>Monthly</h2>\n<svg></svg>"));
+        let feast = page.find("&lt;feast&gt;").unwrap();
+        let refund = page.find("refund").unwrap();
+        assert!(feast < refund && !page.contains("bread"));
+        assert!(page.contains("<td class=\"amount\" data-value=\"-5000\">-50.00€</td>"));
+        assert!(page.contains("<script>") && !page.contains("src="));
+    }
+}
@@ -1,7 +1,10 @@
 use num_traits::FromPrimitive;
 use std::fmt;
 
+use crate::cli::color::Palette;
+use crate::export;
 use crate::util::{
+    currency::AmountFmt,
     date::{Between, Date},
     entry::{Amount, Category},
     summary::Summary,
@@ -10,6 +13,25 @@ use crate::util::{
 pub struct Table<'d> {
     title: String,
     data: &'d [Summary],
+    layout: Layout,
+    palette: Palette,
+    amount_fmt: AmountFmt,
+}
+
+/// How a table is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// colored box-drawing grid for the terminal
+    Grid,
+    Csv,
+    Tsv,
+    /// `{"title": ..., "rows": [...]}` with one object per row
+    Json,
+    /// GitHub-flavoured Markdown
+    Markdown,
+    /// `<table>` element with the shades as background colors,
+    /// see `html_document` to make it a page
+    Html,
 }
 
 struct BoxFmt {
@@ -27,6 +49,7 @@ struct ColFmt {
 struct GridFmt {
     labels: ColFmt,
     columns: Vec<ColFmt>,
+    palette: Palette,
 }
 
 impl<'d> Table<'d> {
@@ -34,9 +57,30 @@ impl<'d> Table<'d> {
         Self {
             title: String::new(),
             data,
+            layout: Layout::Grid,
+            palette: Palette::TrueColor,
+            amount_fmt: AmountFmt::default(),
         }
     }
 
+    /// Colors of the grid, see `Palette::detect`
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Format of the amounts of the grid, Markdown and HTML layouts,
+    /// the others write plain decimal numbers
+    pub fn with_amount_fmt(mut self, fmt: AmountFmt) -> Self {
+        self.amount_fmt = fmt;
+        self
+    }
+
     pub fn with_title<S>(mut self, s: S) -> Self
     where
         S: ToString,
@@ -70,16 +114,16 @@ impl<'d> Table<'d> {
             .map(Statistics::make_shader)
             .collect::<Vec<_>>();
         let shader_total = shader_total.make_shader();
-        let mut grid = GridFmt::with_columns(BoxFmt::from(&self.title), cols);
+        let mut grid = GridFmt::with_columns(BoxFmt::from(&self.title), cols, self.palette);
         for sum in self.data {
             grid.push_line(
-                BoxFmt::period(sum.period()),
+                BoxFmt::period(sum.period(), sum.is_partial()),
                 sum.amounts()
                     .iter()
                     .enumerate()
-                    .map(|(i, f)| BoxFmt::amount(*f).with_shade(shaders[i].generate(f.0 as f64)))
+                    .map(|(i, f)| BoxFmt::amount(*f, &self.amount_fmt).with_shade(shaders[i].generate(f.0 as f64)))
                     .chain(std::iter::once(
-                        BoxFmt::amount(sum.total())
+                        BoxFmt::amount(sum.total(), &self.amount_fmt)
                             .with_shade(shader_total.generate(sum.total().0 as f64)),
                     ))
                     .collect::<Vec<_>>(),
@@ -87,6 +131,63 @@ impl<'d> Table<'d> {
         }
         grid
     }
+
+    /// Names of the columns of the raw layouts
+    fn headers() -> Vec<String> {
+        let categories = (0..Category::COUNT).map(|i| Category::from_usize(i).unwrap().keyword().to_string());
+        ["start", "end", "partial"]
+            .iter()
+            .map(|s| s.to_string())
+            .chain(categories)
+            .chain(std::iter::once(String::from("Total")))
+            .collect()
+    }
+
+    /// Start, end, whether the period is cut to the data, subtotals and total
+    /// of each row, without formatting
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.data
+            .iter()
+            .map(|sum| {
+                let Between(start, end) = sum.period();
+                [export::iso_date(start), export::iso_date(end), sum.is_partial().to_string()]
+                    .into_iter()
+                    .chain(sum.amounts().iter().map(|a| export::decimal(*a)))
+                    .chain(std::iter::once(export::decimal(sum.total())))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn delimited(&self, f: &mut fmt::Formatter, sep: &str) -> fmt::Result {
+        writeln!(f, "{}", Self::headers().join(sep))?;
+        for row in self.rows() {
+            writeln!(f, "{}", row.join(sep))?;
+        }
+        Ok(())
+    }
+
+    fn json(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let headers = Self::headers();
+        writeln!(f, "{{")?;
+        writeln!(f, "  \"title\": \"{}\",", self.title.replace('\\', "\\\\").replace('"', "\\\""))?;
+        write!(f, "  \"rows\": [")?;
+        for (i, row) in self.rows().into_iter().enumerate() {
+            let fields = headers
+                .iter()
+                .zip(row)
+                .enumerate()
+                // dates are strings, `partial` is a boolean and amounts are numbers
+                .map(|(j, (h, v))| if j < 2 { format!("\"{}\": \"{}\"", h, v) } else { format!("\"{}\": {}", h, v) })
+                .collect::<Vec<_>>();
+            write!(f, "{}\n    {{{}}}", if i > 0 { "," } else { "" }, fields.join(", "))?;
+        }
+        if !self.data.is_empty() {
+            write!(f, "\n  ")?;
+        }
+        writeln!(f, "]")?;
+        write!(f, "}}")
+    }
 }
 
 impl BoxFmt {
@@ -103,10 +204,10 @@ impl BoxFmt {
         }
     }
 
-    fn amount(a: Amount) -> Self {
+    fn amount(a: Amount, fmt: &AmountFmt) -> Self {
         if a != Amount(0) {
-            let text = format!("{}", a);
-            let width = text.len() - 2;
+            let text = fmt.format(a);
+            let width = text.chars().count();
             Self {
                 text,
                 width,
@@ -117,12 +218,17 @@ impl BoxFmt {
         }
     }
 
-    fn period(p: Between<Date>) -> Self {
-        Self::from(format!("{}", p))
+    /// Partial periods are marked as such
+    fn period(p: Between<Date>, partial: bool) -> Self {
+        if partial {
+            Self::from(format!("{} (partial)", p))
+        } else {
+            Self::from(format!("{}", p))
+        }
     }
 
     fn category(c: Category) -> Self {
-        Self::from(format!("{:?}", c))
+        Self::from(c.keyword().to_string())
     }
 
     fn with_shade(mut self, shade: Color) -> Self {
@@ -147,10 +253,11 @@ impl ColFmt {
 }
 
 impl GridFmt {
-    fn with_columns(title: BoxFmt, columns: Vec<ColFmt>) -> Self {
+    fn with_columns(title: BoxFmt, columns: Vec<ColFmt>, palette: Palette) -> Self {
         Self {
             labels: ColFmt::with_label(title),
             columns,
+            palette,
         }
     }
 
@@ -164,7 +271,14 @@ impl GridFmt {
 
 impl fmt::Display for Table<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_formatter())
+        match self.layout {
+            Layout::Grid => write!(f, "{}", self.to_formatter()),
+            Layout::Csv => self.delimited(f, ","),
+            Layout::Tsv => self.delimited(f, "\t"),
+            Layout::Json => self.json(f),
+            Layout::Markdown => self.to_formatter().markdown(f),
+            Layout::Html => self.to_formatter().html(f),
+        }
     }
 }
 
@@ -180,10 +294,10 @@ impl fmt::Display for GridFmt {
         writeln!(f, "{}", URCORNER)?;
         // title line
         write!(f, "{}", VLINE)?;
-        self.labels.write_label(f, false)?;
+        self.labels.write_label(f, false, self.palette)?;
         for c in &self.columns {
             write!(f, "{}", VLINE)?;
-            c.write_label(f, true)?;
+            c.write_label(f, true, self.palette)?;
         }
         writeln!(f, "{}", VLINE)?;
         // separator
@@ -198,10 +312,10 @@ impl fmt::Display for GridFmt {
         // main block
         for idx in 0..self.labels.len() {
             write!(f, "{}", VLINE)?;
-            self.labels.write_item(f, idx, false)?;
+            self.labels.write_item(f, idx, false, self.palette)?;
             for c in &self.columns {
                 write!(f, "{}", VLINE)?;
-                c.write_item(f, idx, true)?;
+                c.write_item(f, idx, true, self.palette)?;
             }
             writeln!(f, "{}", VLINE)?;
         }
@@ -217,13 +331,117 @@ impl fmt::Display for GridFmt {
     }
 }
 
+impl GridFmt {
+    fn markdown(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter, cells: Vec<(&BoxFmt, usize)>| {
+            write!(f, "|")?;
+            for (i, (b, width)) in cells.into_iter().enumerate() {
+                // same alignment as in the terminal: labels left, amounts right
+                if i == 0 {
+                    write!(f, " {:<width$} |", b.text, width = width)?;
+                } else {
+                    write!(f, " {:>width$} |", b.text, width = width)?;
+                }
+            }
+            writeln!(f)
+        };
+        let columns = std::iter::once(&self.labels).chain(&self.columns).collect::<Vec<_>>();
+        let widths = columns.iter().map(|c| c.char_width()).collect::<Vec<_>>();
+        row(f, columns.iter().zip(&widths).map(|(c, w)| (&c.label, *w)).collect())?;
+        write!(f, "|")?;
+        for (i, w) in widths.iter().enumerate() {
+            if i == 0 {
+                write!(f, ":{}|", "-".repeat(w + 1))?;
+            } else {
+                write!(f, "{}:|", "-".repeat(w + 1))?;
+            }
+        }
+        writeln!(f)?;
+        for idx in 0..self.labels.len() {
+            row(f, columns.iter().zip(&widths).map(|(c, w)| (&c.boxes[idx], *w)).collect())?;
+        }
+        Ok(())
+    }
+
+    fn html(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cell = |f: &mut fmt::Formatter, tag: &str, b: &BoxFmt, right: bool| {
+            write!(f, "<{}", tag)?;
+            if right {
+                write!(f, " class=\"amount\"")?;
+            }
+            // empty cells are not colored in the terminal either
+            match b.color {
+                Some(c) if !b.text.is_empty() => write!(f, " style=\"background-color: {}\"", c.hex())?,
+                _ => (),
+            }
+            write!(f, ">{}</{}>", escape(&b.text), tag)
+        };
+        writeln!(f, "<table>")?;
+        write!(f, "  <tr>")?;
+        cell(f, "th", &self.labels.label, false)?;
+        for c in &self.columns {
+            cell(f, "th", &c.label, true)?;
+        }
+        writeln!(f, "</tr>")?;
+        for idx in 0..self.labels.len() {
+            write!(f, "  <tr>")?;
+            cell(f, "td", &self.labels.boxes[idx], false)?;
+            for c in &self.columns {
+                cell(f, "td", &c.boxes[idx], true)?;
+            }
+            writeln!(f, "</tr>")?;
+        }
+        writeln!(f, "</table>")
+    }
+}
+
+/// Escape the characters of `text` that have a meaning in HTML
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Standalone page around HTML tables
+pub fn html_document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{}</title>
+<style>
+  table {{ border-collapse: collapse; font-family: monospace; margin-bottom: 1em; }}
+  th, td {{ border: 1px solid #888; padding: 2px 8px; text-align: left; }}
+  .amount {{ text-align: right; }}
+  th[data-sort] {{ cursor: pointer; }}
+  svg {{ max-width: 100%; }}
+  svg .bucket:hover {{ fill-opacity: 0.15; }}
+</style>
+</head>
+<body>
+{}</body>
+</html>
+",
+        escape(title),
+        body
+    )
+}
+
 impl ColFmt {
-    fn write_label(&self, f: &mut fmt::Formatter, right: bool) -> fmt::Result {
-        self.label.write(f, self.width, right)
+    /// Width in characters rather than in terminal columns
+    fn char_width(&self) -> usize {
+        std::iter::once(&self.label)
+            .chain(&self.boxes)
+            .map(|b| b.text.chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn write_label(&self, f: &mut fmt::Formatter, right: bool, palette: Palette) -> fmt::Result {
+        self.label.write(f, self.width, right, palette)
     }
 
-    fn write_item(&self, f: &mut fmt::Formatter, idx: usize, right: bool) -> fmt::Result {
-        self.boxes[idx].write(f, self.width, right)
+    fn write_item(&self, f: &mut fmt::Formatter, idx: usize, right: bool, palette: Palette) -> fmt::Result {
+        self.boxes[idx].write(f, self.width, right, palette)
     }
 
     fn len(&self) -> usize {
@@ -249,9 +467,9 @@ const LOJOIN: &str = "┬";
 const CROSS: &str = "┼";
 const MARGIN: usize = 1;
 impl BoxFmt {
-    fn write(&self, f: &mut fmt::Formatter, width: usize, right: bool) -> fmt::Result {
+    fn write(&self, f: &mut fmt::Formatter, width: usize, right: bool, palette: Palette) -> fmt::Result {
         if let Some(c) = self.color {
-            write!(f, "{}", c)?;
+            write!(f, "{}", palette.foreground(c))?;
         }
         if right {
             write!(
@@ -268,7 +486,7 @@ impl BoxFmt {
                 &PADDING[..(width + MARGIN).saturating_sub(self.width)],
             )?;
         }
-        write!(f, "{}", Color::BLANK)
+        write!(f, "{}", palette.reset())
     }
 }
 
@@ -312,8 +530,8 @@ impl Statistics {
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct Color(u8, u8, u8);
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Color(pub u8, pub u8, pub u8);
 
 pub struct Shader {
     positive: Vec<(f64, Color)>,
@@ -363,7 +581,7 @@ impl Shader {
                 .enumerate()
                 .map(|(i, f)| (f, shades[indexer(i)]))
                 .collect::<Vec<_>>();
-            let delta = arr.get(0).map(|(f, _)| *f).unwrap_or(0.0)
+            let delta = arr.first().map(|(f, _)| *f).unwrap_or(0.0)
                 - arr.last().map(|(f, _)| *f).unwrap_or(0.0);
             if let Some(f) = arr.get_mut(0) {
                 f.0 += delta;
@@ -397,6 +615,50 @@ impl Shader {
 
 impl Color {
     pub const BLANK: &'static str = "\x1b[0m";
+
+    /// Closest color of the 6x6x6 cube of 256-color terminals
+    pub fn ansi256(&self) -> u8 {
+        let level = |c: u8| (c as u16 * 5 + 127) / 255;
+        (16 + 36 * level(self.0) + 6 * level(self.1) + level(self.2)) as u8
+    }
+
+    /// SGR code of the closest of the 16 basic colors
+    pub fn ansi16(&self) -> u8 {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 0, 0),
+            (0, 205, 0),
+            (205, 205, 0),
+            (0, 0, 238),
+            (205, 0, 205),
+            (0, 205, 205),
+            (229, 229, 229),
+            (127, 127, 127),
+            (255, 0, 0),
+            (0, 255, 0),
+            (255, 255, 0),
+            (92, 92, 255),
+            (255, 0, 255),
+            (0, 255, 255),
+            (255, 255, 255),
+        ];
+        let dist = |(r, g, b): (u8, u8, u8)| {
+            let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+            d(r, self.0) + d(g, self.1) + d(b, self.2)
+        };
+        let idx = (0..16).min_by_key(|&i| dist(BASIC[i])).unwrap() as u8;
+        // 30-37 then the bright variants 90-97
+        if idx < 8 {
+            30 + idx
+        } else {
+            90 + idx - 8
+        }
+    }
+
+    /// `#rrggbb` notation of CSS
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
 }
 
 impl fmt::Display for Color {
@@ -404,3 +666,104 @@ impl fmt::Display for Color {
         write!(f, "\x1b[38;2;{};{};{}m", self.0, self.1, self.2)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::{date::Convention, date::Month::*, entry::Entry, entry::Tag};
+
+    fn summaries() -> Vec<Summary> {
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let mut sum = Summary::from_period(Between(dt(Mar, 1), dt(Mar, 31)));
+        sum += &Entry::from(Amount(-1250), Category::Food, Between(dt(Mar, 2), dt(Mar, 2)), Tag(String::new()));
+        vec![sum, Summary::from_period(Between(dt(Apr, 1), dt(Apr, 30)))]
+    }
+
+    #[test]
+    fn csv() {
+        let data = summaries();
+        let text = Table::from(&data).with_layout(Layout::Csv).to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "start,end,partial,Pay,Home,Pro,Clean,Mov,Tech,Food,Fun,Total");
+        assert!(lines[1].starts_with("2021-03-01,2021-03-31,false,"));
+        assert!(!text.contains('\x1b'));
+        let tsv = Table::from(&data).with_layout(Layout::Tsv).to_string();
+        assert_eq!(tsv.replace('\t', ","), text);
+    }
+
+    #[test]
+    fn json() {
+        let data = summaries();
+        let text = Table::from(&data).with_title("Monthly").with_layout(Layout::Json).to_string();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["title"], "Monthly");
+        let rows = value["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["end"], "2021-03-31");
+        assert_eq!(rows[0]["partial"], false);
+        // spending is counted positively, as in the other layouts
+        assert_eq!(rows[0]["Food"].as_f64().unwrap(), 12.5);
+        assert_eq!(rows[0]["Total"], rows[0]["Food"]);
+        let empty = Table::from(&[]).with_layout(Layout::Json).to_string();
+        assert!(serde_json::from_str::<serde_json::Value>(&empty).is_ok());
+    }
+
+    #[test]
+    fn palettes() {
+        let data = summaries();
+        let plain = Table::from(&data).with_palette(Palette::Plain).to_string();
+        assert!(!plain.contains('\x1b'));
+        let ansi = Table::from(&data).with_palette(Palette::Ansi256).to_string();
+        assert!(ansi.contains("\x1b[38;5;") && !ansi.contains("\x1b[38;2;"));
+        assert_eq!(crate::cli::color::strip(&ansi), plain);
+        assert_eq!(Color(255, 0, 0).ansi256(), 196);
+        assert_eq!(Color(255, 0, 0).ansi16(), 91);
+        assert_eq!(Color(0, 255, 43).ansi16(), 92);
+    }
+
+    #[test]
+    fn markdown() {
+        let data = summaries();
+        let text = Table::from(&data).with_title("Monthly").with_layout(Layout::Markdown).to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("| Monthly "));
+        assert!(lines[1].starts_with("|:--"));
+        assert!(lines[1].ends_with("-:|"));
+        // all rows have the same width
+        assert!(lines.iter().all(|l| l.chars().count() == lines[0].chars().count()));
+        assert!(lines[2].contains(" 12.50€ |"));
+        let fr = AmountFmt::default().with_decimal(',').with_symbol(" €");
+        let text = Table::from(&data).with_layout(Layout::Markdown).with_amount_fmt(fr).to_string();
+        assert!(text.contains(" 12,50 € |"));
+    }
+
+    #[test]
+    fn html() {
+        let data = summaries();
+        let text = Table::from(&data).with_title("Monthly").with_layout(Layout::Html).to_string();
+        assert!(text.starts_with("<table>\n  <tr><th>Monthly</th><th class=\"amount\">Pay</th>"));
+        assert_eq!(text.matches("<tr>").count(), 3);
+        assert_eq!(text.matches("background-color: #").count(), 2);
+        let page = html_document("Report", &text);
+        assert!(page.contains("<title>Report</title>") && page.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn partial() {
+        use crate::util::{entry::Duration, summary::Calendar};
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let cal = Calendar::from_aligned(Between(dt(Mar, 15), dt(Apr, 30)), Duration::Month, 1, Convention::default());
+        let text = Table::from(cal.contents()).with_palette(Palette::Plain).to_string();
+        assert!(text.contains("2021-Mar-15..31 (partial)"));
+        assert_eq!(text.matches("(partial)").count(), 1);
+        // raw layouts keep exact dates and a column of their own
+        let csv = Table::from(cal.contents()).with_layout(Layout::Csv).to_string();
+        assert!(csv.contains("2021-03-15,2021-03-31,true,") && csv.contains("2021-04-01,2021-04-30,false,"));
+        let json = Table::from(cal.contents()).with_layout(Layout::Json).to_string();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["rows"][0]["partial"], true);
+        assert_eq!(value["rows"][1]["partial"], false);
+    }
+}
@@ -0,0 +1,142 @@
+//! Input format of `beancount`
+//!
+//! ```txt
+//! 2021-01-01 open Assets:Checking
+//! 2021-01-01 open Expenses:Home
+//!
+//! 2021-03-01 * "Rent"
+//!   billig-period: "2021-03-01..2021-03-31"
+//!   Expenses:Home                             650.00 EUR
+//!   Assets:Checking
+//! ```
+//!
+//! The period of each entry is kept as metadata so that the transactions
+//! can be imported back without losing how they are spread.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::export;
+use crate::util::{
+    date::Between,
+    entry::{Amount, Entry},
+};
+
+/// Metadata key that holds the period of an entry
+pub const PERIOD_KEY: &str = "billig-period";
+
+pub struct Beancount<'e> {
+    entries: &'e [Entry],
+    account: String,
+    commodity: String,
+}
+
+impl<'e> Beancount<'e> {
+    pub fn from(entries: &'e [Entry]) -> Self {
+        Self {
+            entries,
+            account: String::from("Assets:Checking"),
+            commodity: String::from("EUR"),
+        }
+    }
+
+    /// Account that pays for expenses and receives incomes
+    pub fn with_account<S>(mut self, s: S) -> Self
+    where
+        S: ToString,
+    {
+        self.account = s.to_string();
+        self
+    }
+
+    pub fn with_commodity<S>(mut self, s: S) -> Self
+    where
+        S: ToString,
+    {
+        self.commodity = s.to_string();
+        self
+    }
+
+    fn posting(&self, f: &mut fmt::Formatter<'_>, account: &str, amount: Option<Amount>) -> fmt::Result {
+        match amount {
+            Some(amount) => {
+                let amount = format!("{} {}", export::decimal(amount), self.commodity);
+                writeln!(f, "  {:<36}{:>16}", account, amount)
+            }
+            None => writeln!(f, "  {}", account),
+        }
+    }
+}
+
+impl fmt::Display for Beancount<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.period().0);
+        let first = match entries.first() {
+            Some(entry) => entry.period().0,
+            None => return Ok(()),
+        };
+        // beancount refuses postings to accounts that were never opened
+        let mut accounts = entries
+            .iter()
+            .map(|e| export::account(e.category()))
+            .collect::<BTreeSet<_>>();
+        accounts.insert(self.account.clone());
+        for account in accounts {
+            writeln!(f, "{} open {}", export::iso_date(first), account)?;
+        }
+        for entry in entries {
+            writeln!(f)?;
+            let Between(start, end) = entry.period();
+            writeln!(f, "{} * \"{}\"", export::iso_date(start), export::description(entry))?;
+            writeln!(
+                f,
+                "  {}: \"{}..{}\"",
+                PERIOD_KEY,
+                export::iso_date(start),
+                export::iso_date(end)
+            )?;
+            // expenses are negative in billig but increase the balance of their account
+            self.posting(f, &export::account(entry.category()), Some(-entry.value()))?;
+            self.posting(f, &self.account, None)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::{
+        date::{Date, Month::*},
+        entry::{Category, Tag},
+    };
+
+    #[test]
+    fn transactions() {
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let entries = vec![
+            Entry::from(Amount(-65000), Category::Home, Between(dt(Mar, 1), dt(Mar, 31)), Tag(String::from("Rent"))),
+            Entry::from(Amount(210000), Category::Salary, Between(dt(Feb, 26), dt(Feb, 26)), Tag(String::from("Pay"))),
+        ];
+        let text = Beancount::from(&entries).to_string();
+        assert_eq!(
+            text,
+            "\
+2021-02-26 open Assets:Checking
+2021-02-26 open Expenses:Home
+2021-02-26 open Income:Salary
+
+2021-02-26 * \"Pay\"
+  billig-period: \"2021-02-26..2021-02-26\"
+  Income:Salary                           -2100.00 EUR
+  Assets:Checking
+
+2021-03-01 * \"Rent\"
+  billig-period: \"2021-03-01..2021-03-31\"
+  Expenses:Home                             650.00 EUR
+  Assets:Checking
+"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Journal format of `ledger` and `hledger`
+//!
+//! ```txt
+//! 2021-03-01 Rent
+//!     Expenses:Home                             650.00 EUR
+//!     Assets:Checking
+//! ```
+//!
+//! With amortization the whole amount first goes to a deferral account,
+//! from which it is transferred to the category account over the period:
+//!
+//! ```txt
+//! 2021-03-01 Rent
+//!     Assets:Deferred:Home                      650.00 EUR
+//!     Assets:Checking
+//!
+//! 2021-03-01 Rent (1/31)
+//!     Expenses:Home                              20.97 EUR
+//!     Assets:Deferred:Home
+//! ```
+
+use std::fmt;
+
+use crate::export::{self, Amortization};
+use crate::util::entry::{Amount, Entry};
+
+pub struct Journal<'e> {
+    entries: &'e [Entry],
+    amortization: Amortization,
+    account: String,
+    commodity: String,
+}
+
+impl<'e> Journal<'e> {
+    pub fn from(entries: &'e [Entry]) -> Self {
+        Self {
+            entries,
+            amortization: Amortization::None,
+            account: String::from("Assets:Checking"),
+            commodity: String::from("EUR"),
+        }
+    }
+
+    pub fn with_amortization(mut self, amortization: Amortization) -> Self {
+        self.amortization = amortization;
+        self
+    }
+
+    /// Account that pays for expenses and receives incomes
+    pub fn with_account<S>(mut self, s: S) -> Self
+    where
+        S: ToString,
+    {
+        self.account = s.to_string();
+        self
+    }
+
+    pub fn with_commodity<S>(mut self, s: S) -> Self
+    where
+        S: ToString,
+    {
+        self.commodity = s.to_string();
+        self
+    }
+
+    fn posting(&self, f: &mut fmt::Formatter<'_>, account: &str, amount: Option<Amount>) -> fmt::Result {
+        match amount {
+            Some(amount) => {
+                let amount = format!("{} {}", export::decimal(amount), self.commodity);
+                writeln!(f, "    {:<36}{:>16}", account, amount)
+            }
+            None => writeln!(f, "    {}", account),
+        }
+    }
+}
+
+impl fmt::Display for Journal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.period().0);
+        let mut first = true;
+        for entry in entries {
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            let account = export::account(entry.category());
+            let description = export::description(entry);
+            // expenses are negative in billig but increase the balance of their account
+            let value = -entry.value();
+            let pieces = export::pieces(entry, self.amortization);
+            writeln!(f, "{} {}", export::iso_date(entry.period().0), description)?;
+            if pieces.len() <= 1 {
+                self.posting(f, &account, Some(value))?;
+                self.posting(f, &self.account, None)?;
+                continue;
+            }
+            let deferred = account.replacen("Expenses", "Assets:Deferred", 1).replacen("Income", "Liabilities:Deferred", 1);
+            self.posting(f, &deferred, Some(value))?;
+            self.posting(f, &self.account, None)?;
+            let count = pieces.len();
+            for (i, piece) in pieces.into_iter().enumerate() {
+                writeln!(f)?;
+                writeln!(f, "{} {} ({}/{})", export::iso_date(piece.period().0), description, i + 1, count)?;
+                self.posting(f, &account, Some(-piece.value()))?;
+                self.posting(f, &deferred, None)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::{
+        date::{Between, Date, Month::*},
+        entry::{Category, Tag},
+    };
+
+    #[test]
+    fn single() {
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let entries = vec![
+            Entry::from(Amount(-65000), Category::Home, Between(dt(Mar, 1), dt(Mar, 31)), Tag(String::from("Rent"))),
+            Entry::from(Amount(210000), Category::Salary, Between(dt(Feb, 26), dt(Feb, 26)), Tag(String::from("Pay"))),
+        ];
+        let journal = Journal::from(&entries).to_string();
+        assert_eq!(
+            journal,
+            "\
+2021-02-26 Pay
+    Income:Salary                           -2100.00 EUR
+    Assets:Checking
+
+2021-03-01 Rent
+    Expenses:Home                             650.00 EUR
+    Assets:Checking
+"
+        );
+    }
+
+    #[test]
+    fn amortized() {
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let entries = vec![Entry::from(
+            Amount(-9000),
+            Category::Home,
+            Between(dt(Mar, 15), dt(Apr, 14)),
+            Tag(String::from("Rent")),
+        )];
+        let journal = Journal::from(&entries)
+            .with_amortization(Amortization::Monthly)
+            .with_commodity("€")
+            .to_string();
+        assert!(journal.starts_with("2021-03-15 Rent\n    Assets:Deferred:Home "));
+        assert!(journal.contains("2021-04-01 Rent (2/2)\n    Expenses:Home "));
+        assert_eq!(journal.matches("Assets:Deferred:Home").count(), 3);
+    }
+}
@@ -0,0 +1,88 @@
+//! Conversion of entries to the formats of other accounting tools
+
+pub mod beancount;
+pub mod ledger;
+
+use crate::util::{
+    date::{Between, Date},
+    entry::{Amount, Category, Entry},
+};
+
+/// How the spreading of an entry over its period is represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Amortization {
+    /// a single transaction on the first day of the period
+    None,
+    /// one posting per day of the period
+    Daily,
+    /// one posting per month (or part of a month) of the period
+    Monthly,
+}
+
+/// Account of a category, e.g. `Expenses:Food`
+pub fn account(cat: Category) -> String {
+    let kind = if cat.sign() { "Income" } else { "Expenses" };
+    format!("{}:{:?}", kind, cat)
+}
+
+/// Description of an entry, defaults to its category
+pub fn description(entry: &Entry) -> String {
+    entry
+        .tag()
+        .map(|t| t.0.clone())
+        .unwrap_or_else(|| format!("{:?}", entry.category()))
+}
+
+/// Amount with two decimals and no currency symbol
+pub fn decimal(amount: Amount) -> String {
+    format!(
+        "{}{}.{:02}",
+        if amount.0 < 0 { "-" } else { "" },
+        amount.0.abs() / 100,
+        amount.0.abs() % 100
+    )
+}
+
+/// Date as `YYYY-MM-DD`
+pub fn iso_date(date: Date) -> String {
+    format!("{}-{:02}-{:02}", date.year(), date.month() as u8 + 1, date.day())
+}
+
+/// Split an entry into the parts that fall in each day or month of its period
+///
+/// The values of the parts add up to the value of the entry
+pub fn pieces(entry: &Entry, amortization: Amortization) -> Vec<Entry> {
+    let Between(start, end) = entry.period();
+    let mut pieces = Vec::new();
+    let mut date = start;
+    while date <= end {
+        let last = match amortization {
+            Amortization::None => end,
+            Amortization::Daily => date,
+            Amortization::Monthly => date.end_of_month().min(end),
+        };
+        pieces.extend(entry.clone().intersect(Between(date, last)));
+        date = last.next();
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::{date::Month::*, entry::Tag};
+
+    #[test]
+    fn pieces_sum() {
+        let dt = |m, d| Date::from(2021, m, d).unwrap();
+        let entry = Entry::from(Amount(-10000), Category::Home, Between(dt(Jan, 15), dt(Mar, 14)), Tag(String::new()));
+        let monthly = pieces(&entry, Amortization::Monthly);
+        assert_eq!(monthly.len(), 3);
+        assert_eq!(monthly[1].period(), Between(dt(Feb, 1), dt(Feb, 28)));
+        assert_eq!(monthly.iter().map(|e| e.value()).sum::<Amount>(), Amount(-10000));
+        let daily = pieces(&entry, Amortization::Daily);
+        assert_eq!(daily.len(), 59);
+        assert_eq!(daily.iter().map(|e| e.value()).sum::<Amount>(), Amount(-10000));
+        assert_eq!(pieces(&entry, Amortization::None).len(), 1);
+    }
+}
@@ -0,0 +1,331 @@
+//! Transactions of a `beancount` file
+//!
+//! Each posting to an `Expenses` or `Income` account becomes an entry whose
+//! category is read from the name of the account (`Expenses:Food:Groceries`
+//! maps to `Food`), unless an explicit mapping is given for that account.
+//! Postings to other accounts are the counterparts of these and are ignored.
+//!
+//! The period of the entry is read from the `billig-period` metadata if there
+//! is one, as written by `export::beancount`, otherwise the entry lasts a day.
+
+use crate::export::beancount::PERIOD_KEY;
+use crate::import::{read_amount, read_date, Candidate};
+use crate::load::error;
+use crate::util::{
+    date::{Between, Date},
+    entry::{self, Amount, Category, Span},
+};
+
+/// Transaction being read
+struct Pending {
+    line: usize,
+    date: Date,
+    description: String,
+    period: Option<String>,
+    postings: Vec<(String, Option<Amount>)>,
+}
+
+/// Read the file at `path`
+///
+/// `accounts` are explicit mappings from an account and its subaccounts to a category
+pub fn read(path: &str, errs: &mut error::Record, accounts: &[(String, Category)]) -> Option<Vec<Candidate>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => {
+            errs.make("File not found")
+                .text(format!("Beancount file is '{}'", path))
+                .hint("check that the path is correct");
+            return None;
+        }
+    };
+    Some(extract(path, errs, accounts, &contents))
+}
+
+/// Read entries from the contents of a beancount file
+///
+/// Transactions that cannot be read and postings to unknown accounts are reported and skipped
+pub fn extract(path: &str, errs: &mut error::Record, accounts: &[(String, Category)], contents: &str) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let mut pending: Option<Pending> = None;
+    for (line, text) in contents.lines().enumerate() {
+        let indented = text.starts_with(' ') || text.starts_with('\t');
+        let text = strip_comment(text).trim();
+        if text.is_empty() {
+            continue;
+        }
+        if !indented {
+            if let Some(tr) = pending.take() {
+                finish(path, errs, accounts, tr, &mut candidates);
+            }
+            pending = read_header(text, line);
+            continue;
+        }
+        let tr = match pending.as_mut() {
+            Some(tr) => tr,
+            // metadata of other directives
+            None => continue,
+        };
+        if let Some((key, value)) = read_metadata(text) {
+            if key == PERIOD_KEY {
+                tr.period = Some(value.trim_matches('"').to_string());
+            }
+            continue;
+        }
+        let mut words = text.split_whitespace();
+        let mut account = words.next().unwrap();
+        if account == "*" || account == "!" {
+            account = match words.next() {
+                Some(account) => account,
+                None => continue,
+            };
+        }
+        let amount = match words.next() {
+            // costs and prices come after the amount
+            Some(amount) => match read_amount(amount, '.') {
+                Some(amount) => Some(amount),
+                None => {
+                    errs.make("Invalid posting")
+                        .nonfatal()
+                        .text(format!("{}, line {}: '{}' is not an amount", path, line + 1, amount))
+                        .hint("arithmetic expressions are not supported");
+                    pending = None;
+                    continue;
+                }
+            },
+            None => None,
+        };
+        tr.postings.push((account.to_string(), amount));
+    }
+    if let Some(tr) = pending.take() {
+        finish(path, errs, accounts, tr, &mut candidates);
+    }
+    candidates
+}
+
+/// Read `DATE (*|!|txn) ["PAYEE"] "NARRATION"`, other directives are ignored
+fn read_header(text: &str, line: usize) -> Option<Pending> {
+    let mut words = text.splitn(3, char::is_whitespace);
+    let date = read_date(words.next()?, "%Y-%m-%d")?;
+    match words.next()? {
+        "*" | "!" | "txn" => (),
+        _ => return None,
+    }
+    let strings = words
+        .next()
+        .unwrap_or_default()
+        .split('"')
+        .skip(1)
+        .step_by(2)
+        .collect::<Vec<_>>();
+    // the narration is the last string, it can be empty if there is a payee
+    let description = strings
+        .iter()
+        .rev()
+        .find(|s| !s.trim().is_empty())
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default();
+    Some(Pending {
+        line,
+        date,
+        description,
+        period: None,
+        postings: Vec::new(),
+    })
+}
+
+/// Read `key: value`, keys start with a lowercase letter unlike accounts
+fn read_metadata(text: &str) -> Option<(&str, &str)> {
+    let colon = text.find(':')?;
+    let key = &text[..colon];
+    let valid = key.chars().next()?.is_ascii_lowercase()
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Some((key, text[colon + 1..].trim()))
+    } else {
+        None
+    }
+}
+
+fn strip_comment(text: &str) -> &str {
+    // a ';' inside a string does not start a comment
+    let mut quoted = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &text[..i],
+            _ => (),
+        }
+    }
+    text
+}
+
+/// Category of the entries that post to `account`
+///
+/// Accounts that are not `tracked` only have the categories given in `accounts`
+fn category(accounts: &[(String, Category)], account: &str, tracked: bool) -> Option<Category> {
+    let explicit = accounts
+        .iter()
+        .filter(|(prefix, _)| account == prefix || account.starts_with(&format!("{}:", prefix)))
+        .max_by_key(|(prefix, _)| prefix.len());
+    if let Some((_, cat)) = explicit {
+        return Some(*cat);
+    }
+    if !tracked {
+        return None;
+    }
+    // the most specific component that names a category
+    account.split_once(':')?.1.rsplit(':').find_map(|name| {
+        CATEGORIES
+            .iter()
+            .find(|cat| cat.keyword().eq_ignore_ascii_case(name) || format!("{:?}", cat).eq_ignore_ascii_case(name))
+            .copied()
+    })
+}
+
+const CATEGORIES: &[Category] = &[
+    Category::Salary,
+    Category::Home,
+    Category::School,
+    Category::Cleaning,
+    Category::Movement,
+    Category::Tech,
+    Category::Food,
+    Category::Fun,
+];
+
+fn finish(path: &str, errs: &mut error::Record, accounts: &[(String, Category)], tr: Pending, candidates: &mut Vec<Candidate>) {
+    let elided = tr.postings.iter().filter(|(_, a)| a.is_none()).count();
+    if elided > 1 {
+        errs.make("Invalid transaction")
+            .nonfatal()
+            .text(format!("{}, line {}: several postings without an amount", path, tr.line + 1))
+            .hint("at most one posting can have its amount left out");
+        return;
+    }
+    let rest = -tr.postings.iter().filter_map(|(_, a)| *a).sum::<Amount>();
+    let period = match tr.period {
+        None => None,
+        Some(period) => match read_period(&period) {
+            Some(period) => Some(period),
+            None => {
+                errs.make("Invalid period")
+                    .nonfatal()
+                    .text(format!("{}, line {}: '{}' is not a period", path, tr.line + 1, period))
+                    .hint(format!("expected {}: \"YYYY-MM-DD..YYYY-MM-DD\", the entry will last a day", PERIOD_KEY));
+                None
+            }
+        },
+    };
+    for (account, amount) in tr.postings {
+        let tracked = account.starts_with("Expenses:") || account.starts_with("Income:");
+        let cat = match category(accounts, &account, tracked) {
+            Some(cat) => cat,
+            None if tracked => {
+                errs.make("Unknown account")
+                    .nonfatal()
+                    .text(format!("{}, line {}: no category for '{}'", path, tr.line + 1, account))
+                    .hint(format!("map it to a category with --map {}=Food", account));
+                continue;
+            }
+            None => continue,
+        };
+        candidates.push(Candidate {
+            date: tr.date,
+            // expenses increase the balance of their account but are negative in billig
+            value: -amount.unwrap_or(rest),
+            cat,
+            span: Span::from(entry::Duration::Day, entry::Window::Posterior, 1),
+            period,
+            tag: tr.description.replace("\\\"", "'").replace('"', "'"),
+        });
+    }
+}
+
+/// Read `YYYY-MM-DD..YYYY-MM-DD`
+fn read_period(s: &str) -> Option<Between<Date>> {
+    let mut dates = s.splitn(2, "..");
+    let start = read_date(dates.next()?, "%Y-%m-%d")?;
+    let end = read_date(dates.next()?, "%Y-%m-%d")?;
+    if start <= end {
+        Some(Between(start, end))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::beancount::Beancount;
+    use crate::util::date::{Convention, Month::*};
+
+    #[test]
+    fn round_trip() {
+        let mut errs = error::Record::new();
+        let (entries, _) = crate::load::read_source(
+            "test",
+            &mut errs,
+            &std::fs::read_to_string("examples/overview.bil").unwrap(),
+            Convention::default(),
+        );
+        let entries = entries.unwrap();
+        let text = Beancount::from(&entries).to_string();
+        let candidates = extract("test", &mut errs, &[], &text);
+        assert_eq!(errs.count_warnings(), 0);
+        assert_eq!(candidates.len(), entries.len());
+        assert!(candidates.iter().all(|c| c.is_in(&entries, Convention::default())));
+        let (back, _) = crate::load::read_source("test", &mut errs, &crate::import::to_source(&candidates), Convention::default());
+        assert_eq!(back.unwrap().len(), entries.len());
+    }
+
+    #[test]
+    fn mapping() {
+        let src = r#"
+option "title" "Test"
+2021-01-01 open Expenses:Groceries
+
+2021-03-02 * "Shop" "Weekly groceries" #food
+  Expenses:Groceries     45.10 EUR ; comment
+  Expenses:Transport:Bus  2.00 EUR
+  Liabilities:Card
+
+2021-03-05 txn "Unknown"
+  Expenses:Misc  10 EUR
+  Assets:Cash   -10 EUR
+
+2021-03-08 * "Pass"
+  Expenses:Mov         200 EUR
+  Assets:Home:Savings -200 EUR
+"#;
+        let mut errs = error::Record::new();
+        let map = vec![(String::from("Expenses:Groceries"), Category::Food)];
+        let candidates = extract("test", &mut errs, &map, src);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(errs.count_warnings(), 2);
+        let c = &candidates[0];
+        assert_eq!(c.date, Date::from(2021, Mar, 2).unwrap());
+        assert_eq!(c.value, Amount(-4510));
+        assert_eq!(c.tag, "Weekly groceries");
+        // named after a category, but only expenses and income are tracked
+        assert_eq!((candidates[1].cat, candidates[1].value), (Category::Movement, Amount(-20000)));
+
+        let mut errs = error::Record::new();
+        let map = vec![
+            (String::from("Expenses"), Category::Fun),
+            (String::from("Expenses:Transport"), Category::Movement),
+        ];
+        let candidates = extract("test", &mut errs, &map, src);
+        assert_eq!(candidates.len(), 4);
+        assert_eq!(candidates[1].cat, Category::Movement);
+        assert_eq!(candidates[2].cat, Category::Fun);
+        assert_eq!(candidates[3].cat, Category::Fun);
+        assert_eq!(errs.count_warnings(), 0);
+
+        let mut errs = error::Record::new();
+        let map = vec![(String::from("Assets:Home"), Category::Home)];
+        let candidates = extract("test", &mut errs, &map, src);
+        let home = candidates.iter().filter(|c| c.cat == Category::Home).collect::<Vec<_>>();
+        assert_eq!(home.len(), 1);
+        assert_eq!(home[0].value, Amount(20000));
+    }
+}
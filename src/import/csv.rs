@@ -0,0 +1,118 @@
+//! Comma-separated (or semicolon-separated, ...) statements
+
+use crate::import::{read_amount, read_date, Transaction};
+use crate::load::{
+    error,
+    rules::{Amounts, Profile},
+};
+use crate::util::entry::Amount;
+
+/// Read the statement at `path` according to the layout described by `profile`
+///
+/// Rows that cannot be read are reported and skipped
+pub fn read(path: &str, errs: &mut error::Record, profile: &Profile) -> Option<Vec<Transaction>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => {
+            errs.make("File not found")
+                .text(format!("Statement is '{}'", path))
+                .hint("check that the path is correct");
+            return None;
+        }
+    };
+    Some(extract(path, errs, profile, &contents))
+}
+
+/// Read transactions from the contents of a statement
+pub fn extract(path: &str, errs: &mut error::Record, profile: &Profile, contents: &str) -> Vec<Transaction> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(profile.delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let mut transactions = Vec::new();
+    for (line, record) in reader.records().enumerate().skip(profile.header) {
+        let failure = |errs: &mut error::Record, msg: String| {
+            errs.make("Invalid row")
+                .nonfatal()
+                .text(format!("{}, line {}: {}", path, line + 1, msg))
+                .hint("check that the profile matches the layout of the statement");
+        };
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                failure(errs, e.to_string());
+                continue;
+            }
+        };
+        // empty lines at the end of the file
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let field = |i: usize| record.get(i).unwrap_or_default().trim();
+        let (col, fmt) = &profile.date;
+        let date = match read_date(field(*col), fmt) {
+            Some(date) => date,
+            None => {
+                failure(errs, format!("'{}' is not a date of the form '{}'", field(*col), fmt));
+                continue;
+            }
+        };
+        let value = match profile.amount {
+            Amounts::Signed(col) => read_amount(field(col), profile.decimal),
+            Amounts::Split { debit, credit } => {
+                let (debit, credit) = (field(debit), field(credit));
+                match (debit.is_empty(), credit.is_empty()) {
+                    (false, _) => read_amount(debit, profile.decimal).map(|a| Amount(-a.0.abs())),
+                    (true, false) => read_amount(credit, profile.decimal).map(|a| Amount(a.0.abs())),
+                    (true, true) => None,
+                }
+            }
+        };
+        let value = match value {
+            Some(value) => value,
+            None => {
+                failure(errs, String::from("no valid amount"));
+                continue;
+            }
+        };
+        transactions.push(Transaction {
+            date,
+            value,
+            description: field(profile.description).to_string(),
+        });
+    }
+    transactions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::date::{Date, Month::*};
+
+    #[test]
+    fn read_statement() {
+        let profile = Profile {
+            date: (0, String::from("%d/%m/%Y")),
+            description: 1,
+            amount: Amounts::Split { debit: 2, credit: 3 },
+            delimiter: b';',
+            decimal: ',',
+            header: 1,
+            qif_date: None,
+        };
+        let src = "Date;Label;Debit;Credit\n\
+                   02/03/2021;SNCF;45,50;\n\
+                   05/03/2021;\"ACME; Inc\";;1 200,00\n\
+                   31/02/2021;Bogus;1,00;\n";
+        let mut errs = error::Record::new();
+        let trs = extract("test", &mut errs, &profile, src);
+        assert_eq!(trs.len(), 2);
+        assert_eq!(trs[0].date, Date::from(2021, Mar, 2).unwrap());
+        assert_eq!(trs[0].value, Amount(-4550));
+        assert_eq!(trs[1].description, "ACME; Inc");
+        assert_eq!(trs[1].value, Amount(120000));
+        assert_eq!(errs.count_warnings(), 1);
+        assert!(!errs.is_fatal());
+    }
+}
@@ -0,0 +1,236 @@
+//! Conversion of bank statements into `.bil` entries
+//!
+//! Each format provides a reader that produces raw `Transaction`s,
+//! which are then categorised by a set of `Rules` into `Candidate`s
+//! that can be compared against an existing ledger and printed
+//! as `.bil` source.
+
+pub mod beancount;
+pub mod csv;
+pub mod ofx;
+pub mod qif;
+
+use std::collections::BTreeMap;
+
+use crate::load::{error, rules::Rules};
+use num_traits::FromPrimitive;
+
+use crate::util::{
+    date::{Between, Convention, Date, Month},
+    entry::{Amount, Category, Entry, Span, Tag},
+};
+
+/// A line of a statement
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub date: Date,
+    pub value: Amount,
+    pub description: String,
+}
+
+/// A transaction with the fields of an entry
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub date: Date,
+    pub value: Amount,
+    pub cat: Category,
+    pub span: Span,
+    /// exact period of the entry, takes precedence over `span`
+    pub period: Option<Between<Date>>,
+    pub tag: String,
+}
+
+impl Candidate {
+    /// Entry of the candidate, with the span resolved with `conv`
+    pub fn entry(&self, conv: Convention) -> Entry {
+        let period = self.period.unwrap_or_else(|| self.span.period(self.date, conv));
+        Entry::from(self.value, self.cat, period, Tag(self.tag.clone()))
+    }
+
+    /// Whether an equivalent entry already exists: same period, amount and tag
+    pub fn is_in(&self, entries: &[Entry], conv: Convention) -> bool {
+        let entry = self.entry(conv);
+        entries.iter().any(|e| {
+            e.period() == entry.period()
+                && e.value() == entry.value()
+                && e.tag().map(|t| t.0 == self.tag).unwrap_or(false)
+        })
+    }
+
+    /// Fields in `.bil` syntax, without the date
+    pub fn source(&self) -> String {
+        let cents = self.value.0;
+        let timeframe = match self.period {
+            Some(Between(start, end)) => format!("period {}..{}", start, end),
+            None => format!("span {}", self.span),
+        };
+        format!(
+            "val {}{}.{:02}, type {}, {}, tag \"{}\";",
+            if cents < 0 { "-" } else { "" },
+            cents.abs() / 100,
+            cents.abs() % 100,
+            self.cat.keyword(),
+            timeframe,
+            self.tag,
+        )
+    }
+}
+
+/// Apply `rules` to each transaction
+///
+/// Transactions that match no rule are reported as warnings and skipped
+pub fn categorize(errs: &mut error::Record, rules: &Rules, transactions: Vec<Transaction>) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for tr in transactions {
+        // tags cannot contain quotes
+        let description = tr.description.replace('"', "'").trim().to_string();
+        match rules.categorize(&description) {
+            Some(pattern) => candidates.push(Candidate {
+                date: tr.date,
+                value: tr.value,
+                cat: pattern.cat,
+                span: pattern.span,
+                period: None,
+                tag: pattern.tag.clone().unwrap_or(description),
+            }),
+            None => {
+                errs.make("Uncategorised transaction")
+                    .nonfatal()
+                    .text(format!("{} {} '{}' matches no rule", tr.date, tr.value, description))
+                    .hint("add a rule for it, or a catch-all rule '\"\" => ...;'");
+            }
+        }
+    }
+    candidates
+}
+
+/// Drop the candidates that are already in `entries`, return how many were dropped
+pub fn remove_known(candidates: &mut Vec<Candidate>, entries: &[Entry], conv: Convention) -> usize {
+    let total = candidates.len();
+    candidates.retain(|c| !c.is_in(entries, conv));
+    total - candidates.len()
+}
+
+/// Print candidates as nested year/month/day blocks
+pub fn to_source(candidates: &[Candidate]) -> String {
+    let mut days = BTreeMap::<Date, Vec<String>>::new();
+    for c in candidates {
+        days.entry(c.date).or_default().push(c.source());
+    }
+    let mut out = String::new();
+    let mut prev: Option<Date> = None;
+    for (date, entries) in days {
+        if prev.map(|p| p.year() != date.year()).unwrap_or(true) {
+            if prev.is_some() {
+                out.push('\n');
+            }
+            out.push_str(&format!("{}:\n", date.year()));
+        }
+        if prev.map(|p| p.year() != date.year() || p.month() != date.month()).unwrap_or(true) {
+            out.push_str(&format!("    {}:\n", date.month()));
+        }
+        for (i, entry) in entries.iter().enumerate() {
+            if i == 0 {
+                out.push_str(&format!("        {:02}: {}\n", date.day(), entry));
+            } else {
+                out.push_str(&format!("            {}\n", entry));
+            }
+        }
+        prev = Some(date);
+    }
+    out
+}
+
+/// Parse a date with a `chrono` format string
+pub fn read_date(s: &str, fmt: &str) -> Option<Date> {
+    use chrono::Datelike;
+    let date = chrono::NaiveDate::parse_from_str(s, fmt).ok()?;
+    Date::from(date.year() as usize, Month::from_u32(date.month0())?, date.day() as usize).ok()
+}
+
+/// Parse an amount, ignoring currency symbols and thousands separators
+///
+/// The other separator of `.` and `,` is only allowed between groups of three digits
+/// before the decimal one: `1.250` with `decimal` set to `,` is 1250, `12.50` is rejected
+pub fn read_amount(s: &str, decimal: char) -> Option<Amount> {
+    let grouping = if decimal == '.' { ',' } else { '.' };
+    let (int, frac) = s.split_once(decimal).unwrap_or((s, ""));
+    let mut groups = int.split(grouping).skip(1);
+    if frac.contains(grouping) || !groups.all(|g| g.chars().filter(|c| c.is_ascii_digit()).count() == 3) {
+        return None;
+    }
+    let cleaned = s
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '-' || *c == decimal)
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect::<String>();
+    let value = cleaned.parse::<f64>().ok()?;
+    Some(Amount((value * 100.0).round() as isize))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::rules::Profile;
+    use crate::util::date::Month::*;
+
+    #[test]
+    fn source_reparses() {
+        let mut errs = error::Record::new();
+        let rules = Rules::extract("test", &mut errs, "\"SNCF\" => Mov, Week<Curr> 2; \"\" => Food;").unwrap();
+        let tr = |y, m, d, v, s: &str| Transaction {
+            date: Date::from(y, m, d).unwrap(),
+            value: Amount(v),
+            description: s.to_string(),
+        };
+        let candidates = categorize(
+            &mut errs,
+            &rules,
+            vec![
+                tr(2021, Mar, 2, -4550, "SNCF \"TGV\""),
+                tr(2020, Dec, 30, -5, "Bakery"),
+                tr(2021, Mar, 2, 120000, "Salary"),
+            ],
+        );
+        let src = to_source(&candidates);
+        assert!(src.starts_with("2020:\n    Dec:\n        30: val -0.05, type Food, span Day<Post> 1, tag \"Bakery\";\n\n2021:"));
+        let (entries, _) = crate::load::read_source("test", &mut errs, &src, Convention::default());
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(candidates.iter().all(|c| c.is_in(&entries, Convention::default())));
+        assert_eq!(entries[1].tag().unwrap().0, "SNCF 'TGV'");
+    }
+
+    #[test]
+    fn amounts() {
+        assert_eq!(read_amount("-12,50", ','), Some(Amount(-1250)));
+        assert_eq!(read_amount("2 100,00 €", ','), Some(Amount(210000)));
+        assert_eq!(read_amount("2.100,00", ','), Some(Amount(210000)));
+        assert_eq!(read_amount("2,100.00", '.'), Some(Amount(210000)));
+        assert_eq!(read_amount("-650.00", ','), None);
+        assert_eq!(read_amount("-12,50", '.'), None);
+        assert_eq!(read_amount("1,000,00", '.'), None);
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut errs = error::Record::new();
+        let rules = Rules::load("examples/import/rules.bil", &mut errs).unwrap();
+        let trs = ofx::read("examples/import/bank.ofx", &mut errs).unwrap();
+        let mut ofx = categorize(&mut errs, &rules, trs);
+        // the QIF statement is written with US conventions, unlike the CSV one of the profile
+        let profile = Profile {
+            decimal: '.',
+            ..rules.profile.clone()
+        };
+        let trs = qif::read("examples/import/bank.qif", &mut errs, &profile).unwrap();
+        let mut qif = categorize(&mut errs, &rules, trs);
+        let (entries, _) = crate::load::read_source("test", &mut errs, &to_source(&ofx), Convention::default());
+        let entries = entries.unwrap();
+        assert_eq!(remove_known(&mut ofx, &entries, Convention::default()), 3);
+        assert!(ofx.is_empty());
+        // rent and salary are in both statements
+        assert_eq!(remove_known(&mut qif, &entries, Convention::default()), 2);
+        assert_eq!(qif.len(), 2);
+    }
+}
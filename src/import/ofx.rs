@@ -0,0 +1,105 @@
+//! Open Financial Exchange statements (`.ofx`, `.qfx`)
+//!
+//! Both the SGML (1.x, closing tags optional) and XML (2.x) variants
+//! are accepted: only the `<STMTTRN>` blocks are read.
+
+use crate::import::{read_amount, read_date, Transaction};
+use crate::load::error;
+
+/// Read the statement at `path`
+pub fn read(path: &str, errs: &mut error::Record) -> Option<Vec<Transaction>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => {
+            errs.make("File not found")
+                .text(format!("Statement is '{}'", path))
+                .hint("check that the path is correct");
+            return None;
+        }
+    };
+    Some(extract(path, errs, &contents))
+}
+
+/// Read transactions from the contents of a statement
+///
+/// Transactions that lack a date or an amount are reported and skipped
+pub fn extract(path: &str, errs: &mut error::Record, contents: &str) -> Vec<Transaction> {
+    let mut transactions = Vec::new();
+    for (i, block) in contents.split("<STMTTRN>").skip(1).enumerate() {
+        let block = block.split("</STMTTRN>").next().unwrap();
+        let date = field(block, "DTPOSTED").and_then(|d| read_date(d.get(..8)?, "%Y%m%d"));
+        let value = field(block, "TRNAMT").and_then(|a| {
+            let decimal = if a.contains('.') { '.' } else { ',' };
+            read_amount(a, decimal)
+        });
+        let description = match (field(block, "NAME"), field(block, "MEMO")) {
+            (Some(name), _) => name,
+            (None, Some(memo)) => memo,
+            (None, None) => "",
+        };
+        match (date, value) {
+            (Some(date), Some(value)) => transactions.push(Transaction {
+                date,
+                value,
+                description: unescape(description),
+            }),
+            _ => {
+                errs.make("Invalid transaction")
+                    .nonfatal()
+                    .text(format!("{}, transaction {}: missing or invalid DTPOSTED or TRNAMT", path, i + 1))
+                    .hint("the statement may be truncated");
+            }
+        }
+    }
+    transactions
+}
+
+/// Contents of `<TAG>` up to the next tag
+fn field<'s>(block: &'s str, tag: &str) -> Option<&'s str> {
+    let start = block.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let rest = &block[start..];
+    Some(rest[..rest.find('<').unwrap_or(rest.len())].trim())
+}
+
+/// Replace the SGML entities that may appear in text fields
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::{
+        date::{Date, Month::*},
+        entry::Amount,
+    };
+
+    #[test]
+    fn read_fixture() {
+        let mut errs = error::Record::new();
+        let trs = read("examples/import/bank.ofx", &mut errs).unwrap();
+        assert_eq!(trs.len(), 3);
+        assert_eq!(trs[1].date, Date::from(2021, Mar, 2).unwrap());
+        assert_eq!(trs[1].value, Amount(-2347));
+        assert_eq!(trs[1].description, "CB CARREFOUR CITY");
+        assert_eq!(trs[2].value, Amount(210000));
+        assert_eq!(trs[2].description, "VIR SALAIRE MARS");
+        assert_eq!(errs.count_warnings(), 0);
+    }
+
+    #[test]
+    fn xml_variant() {
+        let src = "<STMTTRN><TRNTYPE>DEBIT</TRNTYPE><DTPOSTED>20210305</DTPOSTED>\
+                   <TRNAMT>-45.50</TRNAMT><NAME>SNCF &amp; Co</NAME></STMTTRN>\
+                   <STMTTRN><TRNAMT>-1.00</TRNAMT></STMTTRN>";
+        let mut errs = error::Record::new();
+        let trs = extract("test", &mut errs, src);
+        assert_eq!(trs.len(), 1);
+        assert_eq!(trs[0].description, "SNCF & Co");
+        assert_eq!(errs.count_warnings(), 1);
+    }
+}
@@ -0,0 +1,120 @@
+//! Quicken Interchange Format statements (`.qif`)
+
+use crate::import::{read_amount, read_date, Transaction};
+use crate::load::{error, rules::Profile};
+use crate::util::date::Date;
+
+/// QIF files are most often written with US conventions
+const DATE_FORMATS: &[&str] = &["%m/%d/%Y", "%m/%d/%y", "%m/%d'%y", "%m-%d-%Y", "%Y-%m-%d", "%d.%m.%Y"];
+
+/// Read the statement at `path`
+///
+/// Dates are read with the `qif_date` format of the profile if there is one, otherwise with
+/// the usual QIF formats. Amounts use the decimal separator of the profile.
+pub fn read(path: &str, errs: &mut error::Record, profile: &Profile) -> Option<Vec<Transaction>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => {
+            errs.make("File not found")
+                .text(format!("Statement is '{}'", path))
+                .hint("check that the path is correct");
+            return None;
+        }
+    };
+    Some(extract(path, errs, profile, &contents))
+}
+
+/// Read transactions from the contents of a statement
+///
+/// Records that lack a date or an amount are reported and skipped
+pub fn extract(path: &str, errs: &mut error::Record, profile: &Profile, contents: &str) -> Vec<Transaction> {
+    let mut transactions = Vec::new();
+    let mut date = None;
+    let mut value = None;
+    let mut payee = None;
+    let mut memo = None;
+    for (line, text) in contents.lines().enumerate() {
+        let text = text.trim();
+        let (code, rest) = text.split_at(text.chars().next().map(|c| c.len_utf8()).unwrap_or(0));
+        match code {
+            "D" => date = Some(rest.replace(' ', "")),
+            "T" | "U" => value = read_amount(rest, profile.decimal),
+            "P" => payee = Some(rest.to_string()),
+            "M" => memo = Some(rest.to_string()),
+            "^" => {
+                let parsed = date.take().and_then(|d| read_qif_date(&d, profile.qif_date.as_deref()));
+                match (parsed, value.take()) {
+                    (Some(date), Some(value)) => transactions.push(Transaction {
+                        date,
+                        value,
+                        description: payee.take().or_else(|| memo.take()).unwrap_or_default(),
+                    }),
+                    _ => {
+                        errs.make("Invalid record")
+                            .nonfatal()
+                            .text(format!("{}, record ending line {}: missing or invalid date or amount", path, line + 1))
+                            .hint("check that the date format and decimal separator of the profile match the statement");
+                    }
+                }
+                payee = None;
+                memo = None;
+            }
+            // headers (!Type:Bank), categories, cleared status, ...
+            _ => (),
+        }
+    }
+    transactions
+}
+
+fn read_qif_date(s: &str, date_format: Option<&str>) -> Option<Date> {
+    match date_format {
+        Some(fmt) => read_date(s, fmt),
+        None => DATE_FORMATS.iter().find_map(|fmt| read_date(s, fmt)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::{date::Month::*, entry::Amount};
+
+    #[test]
+    fn read_fixture() {
+        let mut errs = error::Record::new();
+        let trs = read("examples/import/bank.qif", &mut errs, &Profile::default()).unwrap();
+        assert_eq!(trs.len(), 4);
+        assert_eq!(trs[1].date, Date::from(2021, Mar, 5).unwrap());
+        assert_eq!(trs[1].description, "CB SNCF INTERNET");
+        assert_eq!(trs[2].date, Date::from(2021, Mar, 5).unwrap());
+        assert_eq!(trs[3].value, Amount(210000));
+        assert_eq!(errs.count_warnings(), 0);
+    }
+
+    #[test]
+    fn explicit_format() {
+        let mut errs = error::Record::new();
+        let profile = Profile {
+            qif_date: Some(String::from("%d/%m/%Y")),
+            ..Profile::default()
+        };
+        let trs = extract("test", &mut errs, &profile, "D05/03/2021\nT-1\n^\nD03/30/2021\nT-1\n^\n");
+        assert_eq!(trs.len(), 1);
+        assert_eq!(trs[0].date, Date::from(2021, Mar, 5).unwrap());
+        assert_eq!(errs.count_warnings(), 1);
+    }
+
+    #[test]
+    fn comma_decimal() {
+        let mut errs = error::Record::new();
+        let profile = Profile {
+            decimal: ',',
+            ..Profile::default()
+        };
+        let trs = extract("test", &mut errs, &profile, "D03/05/2021\nT-12,50\n^\nD03/26/2021\nT2.100,00\n^\nD03/27/2021\nT-650.00\n^\n");
+        assert_eq!(trs.len(), 2);
+        assert_eq!(trs[0].value, Amount(-1250));
+        assert_eq!(trs[1].value, Amount(210000));
+        // read with the wrong separator, reported instead of being 100 times too large
+        assert_eq!(errs.count_warnings(), 1);
+    }
+}
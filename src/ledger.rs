@@ -0,0 +1,259 @@
+//! Public interface to the contents of a `.bil` file
+//!
+//! `Ledger` wraps the loading pipeline (parsing, template expansion)
+//! and reports failures through `Result`s rather than through a
+//! shared `error::Record`.
+
+use crate::load::{self, error};
+use crate::util::{
+    date::{Between, Convention, Date, Duration, Interval},
+    entry::Entry,
+    summary::{Align, Calendar, Summary},
+};
+
+/// A validated collection of entries
+///
+/// Warnings emitted during loading do not prevent the construction
+/// of a `Ledger`, they are kept and can be inspected with `warnings`.
+#[derive(Debug)]
+pub struct Ledger {
+    entries: Vec<Entry>,
+    /// union of the periods of all entries, possibly restricted
+    timeframe: Interval<Date>,
+    /// nonfatal errors emitted while loading
+    warnings: error::Record,
+    /// boundaries of weeks and years, for spans and calendars
+    convention: Convention,
+}
+
+impl Ledger {
+    /// Read, parse and expand the file at `path` and its imports
+    ///
+    /// On failure the returned `Record` contains at least one fatal error
+    pub fn load(path: &str) -> Result<Self, error::Record> {
+        Self::load_with(path, Convention::default(), error::Record::new())
+    }
+
+    /// Same as `load` with weeks and years that follow `convention`,
+    /// both in the file and in calendars
+    ///
+    /// Diagnostics are added to `errs`, whose levels decide how warnings
+    /// are reported (see `Record::with_levels`)
+    pub fn load_with(path: &str, convention: Convention, mut errs: error::Record) -> Result<Self, error::Record> {
+        match load::read_entries(path, &mut errs, convention) {
+            (Some(entries), timeframe) => Ok(Self {
+                entries,
+                timeframe,
+                warnings: errs,
+                convention,
+            }),
+            (None, _) => Err(errs),
+        }
+    }
+
+    /// Build from already validated entries
+    pub fn from_entries(entries: Vec<Entry>) -> Self {
+        let timeframe = entries.iter().fold(Interval::Empty, |tf, e| {
+            tf.unite(e.period().into_interval())
+        });
+        Self {
+            entries,
+            timeframe,
+            warnings: error::Record::new(),
+            convention: Convention::default(),
+        }
+    }
+
+    /// Use the weeks and years of `convention` in calendars
+    pub fn with_convention(mut self, convention: Convention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Limit the timeframe considered when building calendars
+    pub fn restrict(mut self, period: Interval<Date>) -> Self {
+        self.timeframe = self.timeframe.intersect(period);
+        self
+    }
+
+    /// All entries in the order they were defined
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Range of dates covered by the entries
+    pub fn timeframe(&self) -> Interval<Date> {
+        self.timeframe
+    }
+
+    /// Nonfatal errors emitted while loading
+    pub fn warnings(&self) -> &error::Record {
+        &self.warnings
+    }
+
+    /// Boundaries of weeks and years
+    pub fn convention(&self) -> Convention {
+        self.convention
+    }
+
+    /// Entries that satisfy `pred`
+    pub fn filter<'a, P>(&'a self, pred: P) -> impl Iterator<Item = &'a Entry> + 'a
+    where
+        P: Fn(&Entry) -> bool + 'a,
+    {
+        self.entries.iter().filter(move |e| pred(e))
+    }
+
+    /// Entries truncated to `period`, with their value prorated
+    ///
+    /// Entries that do not overlap `period` are skipped
+    pub fn during(&self, period: Between<Date>) -> impl Iterator<Item = Entry> + '_ {
+        self.entries
+            .iter()
+            .filter_map(move |e| e.clone().intersect(period))
+    }
+
+    /// Aggregate all entries over a single period
+    pub fn summary(&self, period: Between<Date>) -> Summary {
+        let mut sum = Summary::from_period(period);
+        for entry in &self.entries {
+            sum += entry;
+        }
+        sum
+    }
+
+    /// Split the timeframe into steps of `count` times `duration` and
+    /// aggregate the entries over each of them
+    pub fn calendar(&self, duration: Duration, count: usize, align: Align) -> Calendar {
+        let period = self.timeframe.into_between();
+        let mut cal = match align {
+            Align::Natural => Calendar::from_aligned(period, duration, count, self.convention),
+            Align::Start => Calendar::from_spacing(period, duration, count),
+        };
+        cal.register(&self.entries);
+        cal
+    }
+
+    /// Aggregate the entries between consecutive `splits`, which must be increasing,
+    /// the last split is the day after the end of the last period
+    ///
+    /// As with `calendar`, periods do not extend outside of the timeframe
+    pub fn calendar_from_splits(&self, splits: &[Date]) -> Calendar {
+        let Between(start, end) = self.timeframe.into_between();
+        let mut splits = splits.iter().map(|s| (*s).max(start).min(end.next())).collect::<Vec<_>>();
+        // splits outside of the timeframe collapse onto its boundaries
+        splits.dedup();
+        let mut cal = Calendar::from_iter(splits.iter().copied());
+        if let (Some(first), Some(last)) = (splits.first(), splits.last()) {
+            if first < last {
+                // entries outside of the splits are left out
+                let entries = self
+                    .filter(|e| e.period().0 < *last && e.period().1 >= *first)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                cal.register(&entries);
+            }
+        }
+        cal
+    }
+}
+
+/// Read a period from a string such as `2020-Jan..Mar-15`
+///
+/// Partial dates are completed relative to `reference`
+pub fn parse_period(value: &str, reference: Date) -> Result<Interval<Date>, error::Record> {
+    let mut errs = error::Record::new();
+    let pseudo_span = pest::Span::new(value, 0, value.len()).unwrap();
+    let pseudo_path = "cmdline";
+    let pseudo_loc = &(pseudo_path, pseudo_span);
+    Interval::parse(pseudo_path, &mut errs, value)
+        .and_then(|partial| partial.make(&mut errs, pseudo_loc, reference))
+        .ok_or(errs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::error::Level;
+    use crate::util::{date::Month::*, entry::Category};
+
+    #[test]
+    fn load_overview() {
+        let ledger = Ledger::load("examples/overview.bil").unwrap();
+        assert_eq!(ledger.entries().len(), 5);
+        assert_eq!(ledger.warnings().count_errors(), 0);
+        let food = ledger.filter(|e| e.category() == Category::Food).count();
+        assert_eq!(food, 4);
+    }
+
+    #[test]
+    fn load_academic() {
+        let conv = Convention::default().with_year_start(Sep);
+        let ledger = Ledger::load_with("examples/overview.bil", conv, error::Record::new()).unwrap();
+        assert_eq!(ledger.convention(), conv);
+        let yearly = ledger.calendar(Duration::Year, 1, Align::Natural);
+        assert_eq!(yearly.contents().len(), 1);
+        let dt = |y, m, d| Date::from(y, m, d).unwrap();
+        assert_eq!(yearly.contents()[0].period(), Between(dt(2020, Sep, 1), dt(2021, Aug, 31)));
+        let calendar = Ledger::load("examples/overview.bil").unwrap().calendar(Duration::Year, 1, Align::Natural);
+        assert_eq!(calendar.contents().len(), 2);
+    }
+
+    #[test]
+    fn load_levels() {
+        let path = "examples/failures/unused.bil";
+        let levels = |level| std::iter::once((String::from("unused-argument"), level)).collect();
+        let ledger = Ledger::load(path).unwrap();
+        assert!(ledger.warnings().count_warnings() > 0);
+        let errs = error::Record::new().with_levels(&levels(Level::Allow));
+        let ledger = Ledger::load_with(path, Convention::default(), errs).unwrap();
+        assert_eq!(ledger.warnings().count_warnings(), 0);
+        let errs = error::Record::new().with_levels(&levels(Level::Deny));
+        assert!(Ledger::load_with(path, Convention::default(), errs).unwrap_err().is_fatal());
+    }
+
+    #[test]
+    fn load_failure() {
+        let errs = Ledger::load("examples/failures/many.bil").unwrap_err();
+        assert!(errs.is_fatal());
+        let errs = Ledger::load("examples/nonexistent.bil").unwrap_err();
+        assert!(errs.is_fatal());
+    }
+
+    #[test]
+    fn calendar_total() {
+        let ledger = Ledger::load("examples/overview.bil").unwrap();
+        let period = ledger.timeframe().into_between();
+        let whole = ledger.summary(period).total();
+        for align in &[Align::Natural, Align::Start] {
+            let monthly = ledger.calendar(Duration::Month, 1, *align);
+            let sum = monthly.contents().iter().map(|s| s.total()).sum();
+            assert_eq!(whole, sum);
+        }
+        let restricted =
+            ledger.restrict(parse_period("2020-Dec", Date::from(2021, Jan, 1).unwrap()).unwrap());
+        assert_eq!(restricted.calendar(Duration::Month, 1, Align::Natural).contents().len(), 1);
+    }
+
+    #[test]
+    fn calendar_splits() {
+        let ledger = Ledger::load("examples/overview.bil").unwrap();
+        let dt = |y, m, d| Date::from(y, m, d).unwrap();
+        let splits = [dt(2020, Oct, 1), dt(2020, Dec, 1), dt(2021, Jan, 1)];
+        let custom = ledger.calendar_from_splits(&splits);
+        assert_eq!(custom.contents().len(), 2);
+        assert_eq!(custom.contents()[1].period(), Between(dt(2020, Dec, 1), dt(2020, Dec, 31)));
+        let autumn = ledger.summary(Between(dt(2020, Oct, 1), dt(2020, Nov, 30))).total();
+        assert_eq!(custom.contents()[0].total(), autumn);
+        assert!(ledger.calendar_from_splits(&splits[..1]).contents().is_empty());
+        let restricted = ledger.restrict(Interval::Between(dt(2020, Nov, 15), dt(2020, Dec, 15)));
+        let clamped = restricted.calendar_from_splits(&splits);
+        assert_eq!(clamped.contents().len(), 2);
+        assert_eq!(clamped.contents()[0].period(), Between(dt(2020, Nov, 15), dt(2020, Nov, 30)));
+        assert_eq!(clamped.contents()[1].period(), Between(dt(2020, Dec, 1), dt(2020, Dec, 15)));
+        let aligned = restricted.calendar(Duration::Month, 1, Align::Natural);
+        assert_eq!(clamped.contents()[1].total(), aligned.contents()[1].total());
+        let outside = [dt(2021, Feb, 1), dt(2021, Mar, 1)];
+        assert!(restricted.calendar_from_splits(&outside).contents().is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+//! Billig: a command-line DSL budget manager
+//!
+//! This crate is usable both as the `billig` binary and as a library
+//! for tools that need to read `.bil` files without going through the
+//! terminal output.
+//!
+//! The entry point is `Ledger`, which loads a file, gives access to
+//! the entries it contains and aggregates them into `Calendar`s.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use billig::{Ledger, util::{entry::Duration, summary::Align}};
+//!
+//! let ledger = match Ledger::load("expenses.bil") {
+//!     Ok(ledger) => ledger,
+//!     Err(errs) => {
+//!         eprintln!("{}", errs);
+//!         return;
+//!     }
+//! };
+//! let monthly = ledger.calendar(Duration::Month, 1, Align::Natural);
+//! for summary in monthly.contents() {
+//!     println!("{}: {}", summary.period(), summary.total());
+//! }
+//! ```
+
+pub mod cli;
+pub mod export;
+pub mod import;
+pub mod ledger;
+pub mod load;
+pub mod util;
+
+pub use ledger::Ledger;
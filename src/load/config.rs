@@ -0,0 +1,363 @@
+//! Default options read from `billig.toml`
+//!
+//! ```toml
+//! source = "expenses.bil"
+//! color = "auto"
+//! align = "natural"
+//! week-start = "Sun"
+//! year-start = "Sep"
+//!
+//! [report]
+//! durations = ["week", "month", "year"]
+//!
+//! [plot]
+//! durations = ["day"]
+//! dir = "plots"
+//! name = "{source}-{kind}-{duration}.svg"
+//! kind = "bars"
+//! palette = "colorblind"
+//! target = "png"
+//! dpi = 192
+//!
+//! [colors]
+//! Food = "#CC79A7"
+//!
+//! [currency]
+//! symbol = " €"
+//! position = "after"
+//! decimal-sep = ","
+//! group-sep = " "
+//! cents = true
+//!
+//! [lints]
+//! unused-argument = "allow"
+//! ```
+//!
+//! Any TOML syntax is accepted, such as literal strings `symbol = ' €'`
+//! or dotted keys `currency.symbol = "$"` outside of the `[currency]` table.
+//!
+//! The file is looked for in the working directory and its parents,
+//! then in `$XDG_CONFIG_HOME/billig`. Relative paths that it contains
+//! are relative to the directory of the file.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::cli::{plot::PlotKind, raster};
+use crate::load::error::{self, Level, LINTS};
+use crate::util::date::{Month, Weekday};
+
+pub const FILENAME: &str = "billig.toml";
+
+/// Contents of a configuration file
+#[derive(Debug, Default)]
+pub struct Config {
+    /// settings by `table.key`, or `key` for those outside of a table
+    values: HashMap<String, String>,
+    lints: HashMap<String, Level>,
+}
+
+/// Kinds of values that a setting accepts
+enum Kind {
+    Text,
+    /// relative to the configuration file
+    Path,
+    Bool,
+    /// strictly positive
+    Count,
+    Choice(&'static [&'static str]),
+    /// array of strings, stored separated by commas
+    List,
+    /// `#rgb`, `#rrggbb`, `rgb(r, g, b)` or a named color of SVG
+    Color,
+}
+
+const SETTINGS: &[(&str, Kind)] = &[
+    ("source", Kind::Path),
+    ("color", Kind::Choice(&["auto", "always", "never"])),
+    ("align", Kind::Choice(&["natural", "none"])),
+    ("week-start", Kind::Choice(&Weekday::NAMES)),
+    ("year-start", Kind::Choice(&Month::NAMES)),
+    ("report.durations", Kind::List),
+    ("plot.durations", Kind::List),
+    ("plot.dir", Kind::Path),
+    ("plot.name", Kind::Text),
+    ("plot.kind", Kind::Choice(&PlotKind::NAMES)),
+    ("plot.palette", Kind::Choice(&["colorblind", "classic"])),
+    ("plot.target", Kind::Choice(&["svg", "png", "term"])),
+    ("plot.dpi", Kind::Count),
+    ("colors.Pay", Kind::Color),
+    ("colors.Home", Kind::Color),
+    ("colors.Pro", Kind::Color),
+    ("colors.Clean", Kind::Color),
+    ("colors.Mov", Kind::Color),
+    ("colors.Tech", Kind::Color),
+    ("colors.Food", Kind::Color),
+    ("colors.Fun", Kind::Color),
+    ("currency.symbol", Kind::Text),
+    ("currency.position", Kind::Choice(&["before", "after"])),
+    ("currency.decimal-sep", Kind::Text),
+    ("currency.group-sep", Kind::Text),
+    ("currency.cents", Kind::Bool),
+];
+
+impl Config {
+    /// Location of the configuration file, if there is one
+    pub fn find() -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        let local = cwd.ancestors().map(|dir| dir.join(FILENAME)).find(|p| p.is_file());
+        local.or_else(|| {
+            let base = std::env::var_os("XDG_CONFIG_HOME")
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+            Some(base.join("billig").join(FILENAME)).filter(|p| p.is_file())
+        })
+    }
+
+    /// Read the configuration file at `path`
+    pub fn load(path: &Path, errs: &mut error::Record) -> Option<Self> {
+        let name = path.to_string_lossy();
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                errs.make("File not found")
+                    .text(format!("Configuration file is '{}'", name))
+                    .hint("check that the path is correct");
+                return None;
+            }
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::extract(&name, dir, errs, &contents)
+    }
+
+    /// Parse settings from `contents`, relative paths are completed with `dir`
+    pub fn extract(path: &str, dir: &Path, errs: &mut error::Record, contents: &str) -> Option<Self> {
+        let table = match contents.parse::<Value>() {
+            Ok(Value::Table(table)) => table,
+            Ok(_) => unreachable!(),
+            Err(e) => {
+                errs.make("Parsing failure")
+                    .text(format!("In '{}': {}", path, e))
+                    .hint("the configuration file should be valid TOML");
+                return None;
+            }
+        };
+        let mut settings = Vec::new();
+        let mut config = Self::default();
+        for (key, value) in &table {
+            match (key.as_str(), value) {
+                ("lints", Value::Table(lints)) => config.read_lints(path, errs, lints),
+                _ => flatten(key.clone(), value, &mut settings),
+            }
+        }
+        for (name, value) in settings {
+            let kind = match SETTINGS.iter().find(|(n, _)| *n == name) {
+                Some((_, kind)) => kind,
+                None => {
+                    errs.make("Unknown setting")
+                        .nonfatal()
+                        .text(format!("In '{}': '{}' is not a setting and will be ignored", path, name))
+                        .hint("check the spelling and the table it is in");
+                    continue;
+                }
+            };
+            match read_value(kind, dir, value) {
+                Some(v) => {
+                    config.values.insert(name, v);
+                }
+                None => {
+                    let expected = match kind {
+                        Kind::Text | Kind::Path => String::from("a string"),
+                        Kind::Bool => String::from("true or false"),
+                        Kind::Count => String::from("a positive integer"),
+                        Kind::Choice(choices) => format!("one of \"{}\"", choices.join("\", \"")),
+                        Kind::List => String::from("a string or an array of strings"),
+                        Kind::Color => String::from("a color such as \"#0072B2\", \"rgb(0, 114, 178)\" or \"teal\""),
+                    };
+                    errs.make("Invalid setting")
+                        .text(format!("In '{}': '{}' is not a valid value for '{}'", path, value, name))
+                        .hint(format!("expected {}", expected));
+                }
+            }
+        }
+        if errs.is_fatal() {
+            return None;
+        }
+        Some(config)
+    }
+
+    /// Levels of the `[lints]` table, unknown names are reported but kept
+    fn read_lints(&mut self, path: &str, errs: &mut error::Record, lints: &toml::value::Table) {
+        for (name, value) in lints {
+            let level = match value.as_str() {
+                Some("allow") => Level::Allow,
+                Some("warn") => Level::Warn,
+                Some("deny") => Level::Deny,
+                _ => {
+                    errs.make("Invalid setting")
+                        .text(format!("In '{}': '{}' is not a valid level for '{}'", path, value, name))
+                        .hint("expected one of \"allow\", \"warn\", \"deny\"");
+                    continue;
+                }
+            };
+            if !LINTS.contains(&name.as_str()) {
+                errs.make("Unknown lint")
+                    .nonfatal()
+                    .text(format!("In '{}': '{}' is not the name of a warning", path, name))
+                    .hint(format!("expected one of {}", LINTS.join(", ")));
+            }
+            self.lints.insert(name.clone(), level);
+        }
+    }
+
+    /// Value of the setting `name` (`table.key`), lists are separated by commas
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|s| s.as_str())
+    }
+
+    /// Levels of warnings by name
+    pub fn lints(&self) -> &HashMap<String, Level> {
+        &self.lints
+    }
+}
+
+/// Settings of nested tables by `table.key`
+fn flatten<'v>(name: String, value: &'v Value, settings: &mut Vec<(String, &'v Value)>) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                flatten(format!("{}.{}", name, key), value, settings);
+            }
+        }
+        _ => settings.push((name, value)),
+    }
+}
+
+fn read_value(kind: &Kind, dir: &Path, value: &Value) -> Option<String> {
+    match kind {
+        Kind::Text => value.as_str().map(String::from),
+        Kind::Path => value.as_str().map(|p| dir.join(p).to_string_lossy().into_owned()),
+        Kind::Bool => value.as_bool().map(|b| b.to_string()),
+        Kind::Count => value.as_integer().filter(|n| *n > 0).map(|n| n.to_string()),
+        Kind::Choice(choices) => value.as_str().filter(|v| choices.contains(v)).map(String::from),
+        Kind::List => match value {
+            Value::Array(items) => items
+                .iter()
+                .map(Value::as_str)
+                .collect::<Option<Vec<_>>>()
+                .map(|items| items.join(",")),
+            _ => value.as_str().map(String::from),
+        },
+        // as understood by the terminal and .png plots
+        Kind::Color => value.as_str().filter(|v| raster::color(v).is_some()).map(String::from),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn settings() {
+        let src = r##"
+            # comment
+            source = "expenses.bil"
+            year-start = "Sep"
+            [report]
+            durations = ["week", "month",
+                         "year"] # trailing comment
+            [plot]
+            dir = "/tmp/plots"
+            dpi = 192
+            [currency]
+            cents = false
+            [colors]
+            Food = "#abc"
+            [lints]
+            unused-argument = "allow"
+        "##;
+        let mut errs = error::Record::new();
+        let config = Config::extract("test", Path::new("budget"), &mut errs, src).unwrap();
+        assert_eq!(errs.count_warnings(), 0);
+        assert_eq!(config.get("source"), Some(Path::new("budget").join("expenses.bil").to_str().unwrap()));
+        assert_eq!(config.get("report.durations"), Some("week,month,year"));
+        assert_eq!(config.get("plot.dir"), Some("/tmp/plots"));
+        assert_eq!(config.get("plot.dpi"), Some("192"));
+        assert_eq!(config.get("currency.cents"), Some("false"));
+        assert_eq!(config.get("colors.Food"), Some("#abc"));
+        assert_eq!(config.lints().get("unused-argument"), Some(&Level::Allow));
+        assert_eq!(config.get("year-start"), Some("Sep"));
+        assert_eq!(config.get("color"), None);
+    }
+
+    #[test]
+    fn toml_syntax() {
+        let src = r#"
+            colors = { Fun = "red" }
+            [currency]
+            symbol = ' €'
+            group-sep = "\u00a0"
+        "#;
+        let mut errs = error::Record::new();
+        let config = Config::extract("test", Path::new(""), &mut errs, src).unwrap();
+        assert_eq!(errs.count_warnings(), 0);
+        assert_eq!(config.get("currency.symbol"), Some(" €"));
+        assert_eq!(config.get("currency.group-sep"), Some("\u{a0}"));
+        assert_eq!(config.get("colors.Fun"), Some("red"));
+        let mut errs = error::Record::new();
+        let src = "currency.symbol = \"$\"\n[plot]\nkind = 'pie'\n";
+        let config = Config::extract("test", Path::new(""), &mut errs, src).unwrap();
+        assert_eq!(config.get("currency.symbol"), Some("$"));
+        assert_eq!(config.get("plot.kind"), Some("pie"));
+    }
+
+    #[test]
+    fn unknown_lints() {
+        let mut errs = error::Record::new();
+        let src = "[lints]\nunused-argument = \"deny\"\nunused-arguments = \"allow\"\n";
+        let config = Config::extract("test", Path::new(""), &mut errs, src).unwrap();
+        assert_eq!(errs.count_warnings(), 1);
+        assert!(errs.to_string().contains("unused-arguments"));
+        assert_eq!(config.lints().get("unused-argument"), Some(&Level::Deny));
+    }
+
+    #[test]
+    fn category_colors() {
+        let mut errs = error::Record::new();
+        let src = "[colors]\nFun = \"teal\"\nMov = \"rgb(0, 114, 178)\"\n";
+        let config = Config::extract("test", Path::new(""), &mut errs, src).unwrap();
+        assert_eq!(config.get("colors.Fun"), Some("teal"));
+        assert_eq!(config.get("colors.Mov"), Some("rgb(0, 114, 178)"));
+        let mut errs = error::Record::new();
+        assert!(Config::extract("test", Path::new(""), &mut errs, "[colors]\nFood = \"dark green\"\n").is_none());
+        assert!(errs.to_string().contains("'colors.Food'"), "{}", errs);
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let mut errs = error::Record::new();
+        assert!(Config::extract("test", Path::new(""), &mut errs, "[plot]\nfoo = 1\n").is_some());
+        assert_eq!(errs.count_warnings(), 1);
+        for src in &[
+            "color = \"sometimes\"",
+            "week-start = \"Sunday\"",
+            "source = 1",
+            "[currency]\ncents = \"no\"",
+            "[plot]\ndpi = 0",
+            "[plot]\ndpi = \"96\"",
+            "[colors]\nFun = \"#12345\"",
+            "[colors]\nFun = \"light blue\"",
+            "[colors]\nFun = \"rgb(0, 128)\"",
+            "[lints]\nunused-argument = \"maybe\"",
+            "source = \"a\"\nsource = \"b\"",
+            "source = \"a",
+        ] {
+            let mut errs = error::Record::new();
+            assert!(Config::extract("test", Path::new(""), &mut errs, src).is_none(), "{}", src);
+            assert!(errs.is_fatal());
+        }
+    }
+}
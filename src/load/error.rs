@@ -6,7 +6,7 @@
 //!
 //! # Example
 //!
-//! ```rust
+//! ```ignore
 //! errs.make("Unused argument")
 //!     .nonfatal()
 //!     .span(inst_loc, format!("in instanciation of '{}'", inst_name))
@@ -41,6 +41,8 @@
 /// occured and the precise span within that file
 pub type Loc<'i> = (&'i str, pest::Span<'i>);
 
+use std::collections::HashMap;
+
 use crate::load::parse::Rule;
 
 /// Report for a single error
@@ -48,7 +50,7 @@ use crate::load::parse::Rule;
 /// All messages (`label` passed with `new`, arguments of `with_hint`
 /// and `with_text`) should fit in a single line.
 ///
-/// ```rust
+/// ```ignore
 /// // NO
 /// errs.new("Fatal failure\ngeneral message\nspanning several lines\nhint to fix\nnote\nsee
 /// documentation")
@@ -66,6 +68,10 @@ use crate::load::parse::Rule;
 pub struct Error {
     /// determines the error label (warning/error) and the color (yellow/red)
     fatal: bool,
+    /// warning disabled by its `Level`
+    silenced: bool,
+    /// how the error is reported if it is a warning
+    level: Level,
     /// name of the error
     label: String,
     /// at which point of the contents is the counter
@@ -89,12 +95,14 @@ enum Item {
 /// but the structure itself makes no assumption regarding the
 /// spatial or semantic relationship between these errors
 #[must_use]
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Record {
     /// how many are errors in the rest are warnings
     /// counts only `contents[..contents.len()-2]`
     fatal: usize,
     contents: Vec<Error>,
+    /// levels of warnings by name, the others are reported as warnings
+    levels: HashMap<String, Level>,
 }
 
 impl Error {
@@ -113,14 +121,25 @@ impl Error {
     {
         Self {
             fatal: true,
+            silenced: false,
+            level: Level::Warn,
             label: msg.to_string(),
             items: Vec::new(),
         }
     }
 
     /// Mark as a warning rather that a fatal error
+    ///
+    /// The `Level` of the warning can turn it back into an error or hide it
     pub fn nonfatal(&mut self) -> &mut Self {
-        self.fatal = false;
+        match self.level {
+            Level::Allow => {
+                self.fatal = false;
+                self.silenced = true;
+            }
+            Level::Warn => self.fatal = false,
+            Level::Deny => (),
+        }
         self
     }
 
@@ -136,7 +155,7 @@ impl Error {
                 },
                 loc.1.clone(),
             )
-            .with_path(&loc.0.to_string()),
+            .with_path(loc.0),
         ));
         self
     }
@@ -163,10 +182,16 @@ impl Error {
 impl Record {
     /// Initialize a new pool of errors (e.g. to record errors from another file)
     pub fn new() -> Self {
-        Self {
-            fatal: 0,
-            contents: Vec::new(),
-        }
+        Self::default()
+    }
+
+    /// Set the level of warnings by name
+    ///
+    /// Names are the labels of the warnings in lowercase with dashes instead
+    /// of spaces, e.g. `unused-argument`.
+    pub fn with_levels(mut self, levels: &HashMap<String, Level>) -> Self {
+        self.levels = levels.clone();
+        self
     }
 
     /// Checks if any of the recorded errors are fatal
@@ -185,7 +210,8 @@ impl Record {
 
     /// Number of nonfatal errors
     pub fn count_warnings(&self) -> usize {
-        self.contents.len() - self.count_errors()
+        let silenced = self.contents.last().map(|e| e.silenced).unwrap_or(false);
+        self.contents.len() - self.count_errors() - if silenced { 1 } else { 0 }
     }
 
     /// Add a new error to the pool
@@ -196,11 +222,45 @@ impl Record {
         if self.last_is_fatal() {
             self.fatal += 1;
         }
-        self.contents.push(Error::new(msg));
+        // the previous one is complete, it can be dropped if it is not to be reported
+        if self.contents.last().map(|e| e.silenced).unwrap_or(false) {
+            self.contents.pop();
+        }
+        let mut error = Error::new(msg);
+        error.level = self.levels.get(&lint_name(&error.label)).copied().unwrap_or(Level::Warn);
+        self.contents.push(error);
         self.contents.last_mut().unwrap()
     }
 }
 
+/// How a kind of warning is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// not at all
+    Allow,
+    Warn,
+    /// as a fatal error
+    Deny,
+}
+
+/// Name of a warning in the configuration: `Unused argument` is `unused-argument`
+pub fn lint_name(label: &str) -> String {
+    label.to_lowercase().replace(' ', "-")
+}
+
+/// Names of the warnings whose level can be set
+pub const LINTS: &[&str] = &[
+    "unused-argument",
+    "needless-amount",
+    "uncategorised-transaction",
+    "invalid-row",
+    "invalid-record",
+    "invalid-transaction",
+    "invalid-posting",
+    "invalid-period",
+    "unknown-account",
+];
+
 const RED: &str = "\x1b[0;91;1m";
 const YELLOW: &str = "\x1b[0;93;1m";
 const BLUE: &str = "\x1b[0;96;1m";
@@ -208,8 +268,38 @@ const WHITE: &str = "\x1b[0;1m";
 const NONE: &str = "\x1b[0m";
 
 use std::fmt;
+use std::fmt::Write;
+
+/// Remove the escape codes of `text`
+pub fn strip(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip up to the final letter of the sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Plain text, see `Record::colored` for escape codes
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut text = String::new();
+        self.render(&mut text)?;
+        write!(f, "{}", strip(&text))
+    }
+}
+
+impl Error {
+    fn render(&self, f: &mut String) -> fmt::Result {
         let (color, header) = if self.fatal {
             (RED, "--> Error")
         } else {
@@ -261,9 +351,34 @@ impl fmt::Display for Error {
     }
 }
 
+/// Plain text, see `Record::colored` for escape codes
 impl fmt::Display for Record {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.contents.is_empty() {
+        let mut text = String::new();
+        self.render(&mut text)?;
+        write!(f, "{}", strip(&text))
+    }
+}
+
+/// Messages of a `Record` with escape codes for the terminal
+pub struct Colored<'r>(&'r Record);
+
+impl fmt::Display for Colored<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut text = String::new();
+        self.0.render(&mut text)?;
+        write!(f, "{}", text)
+    }
+}
+
+impl Record {
+    /// Display with colors
+    pub fn colored(&self) -> Colored<'_> {
+        Colored(self)
+    }
+
+    fn render(&self, f: &mut String) -> fmt::Result {
+        if self.count_errors() + self.count_warnings() == 0 {
             return Ok(());
         }
         let fatal = self.is_fatal();
@@ -277,11 +392,12 @@ impl fmt::Display for Record {
         for err in self
             .contents
             .iter()
-            .filter(|err| err.fatal == fatal)
+            .filter(|err| err.fatal == fatal && !err.silenced)
             .take(trunc)
         {
             // only print errors with the maximum fatality
-            writeln!(f, "{}", err)?;
+            err.render(f)?;
+            writeln!(f)?;
         }
         if count > trunc {
             writeln!(f, "{} And {} more.", color, count - trunc)?;
@@ -368,10 +484,49 @@ fn rule_rename(r: &Rule) -> String {
         Rule::period_only => "a period [start?..end?]",
         Rule::entry_period => "a period field: `period [start?..end?]`",
         Rule::builtin => "a capitalized identifier",
-        Rule::duration => "a duration Day, Week, Month or Year",
+        Rule::duration => "a duration Day, Week, Month, Quarter, Semester or Year",
         Rule::window => "a window Curr, Post, Ante, Pred or Succ",
         Rule::expense_type => "an expense type Mov, Home, Tech, ...",
         Rule::import => "an external file import",
         Rule::path => "a file path",
+        Rule::profile_field => "a profile field such as `date 0 \"%d/%m/%Y\"`",
+        Rule::profile => "a statement profile `profile { ... }`",
+        Rule::rule => "a categorisation rule `\"pattern\" => fields;`",
+        Rule::rules_program => "a sequence of profiles and categorisation rules",
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn plain_by_default() {
+        let mut errs = Record::new();
+        errs.make("Invalid setting").text("'sometimes' is not a valid value");
+        let plain = errs.to_string();
+        assert!(plain.starts_with("--> Error: Invalid setting\n"));
+        assert!(!plain.contains('\x1b'));
+        let colored = errs.colored().to_string();
+        assert!(colored.contains('\x1b'));
+        assert_eq!(strip(&colored), plain);
+    }
+
+    #[test]
+    fn levels() {
+        let levels = [("unused-argument", Level::Allow), ("needless-amount", Level::Deny)]
+            .iter()
+            .map(|(name, level)| (name.to_string(), *level))
+            .collect::<HashMap<_, _>>();
+        let mut errs = Record::new().with_levels(&levels);
+        errs.make("Unused argument").nonfatal();
+        errs.make("Invalid row").nonfatal();
+        assert_eq!((errs.count_errors(), errs.count_warnings()), (0, 1));
+        errs.make("Needless amount").nonfatal();
+        assert!(errs.is_fatal());
+        // levels only apply to the records they are given to
+        let mut errs = Record::new();
+        errs.make("Unused argument").nonfatal();
+        assert_eq!((errs.count_errors(), errs.count_warnings()), (0, 1));
+    }
+}
@@ -1,13 +1,17 @@
+pub mod config;
 pub mod error;
 pub mod parse;
+pub mod rules;
+pub mod syntax;
 pub mod template;
 
 use crate::util::{
-    date::{Date, Interval},
+    date::{Convention, Date, Interval},
     entry::Entry,
 };
 
-pub fn read_entries(filename: &str, errs: &mut error::Record) -> (Option<Vec<Entry>>, Interval<Date>) {
+/// Spans such as `Week<Curr>` are resolved with the weeks and years of `conv`
+pub fn read_entries(filename: &str, errs: &mut error::Record, conv: Convention) -> (Option<Vec<Entry>>, Interval<Date>) {
     let contents = match std::fs::read_to_string(filename) {
         Ok(contents) => contents,
         Err(_) => {
@@ -17,12 +21,24 @@ pub fn read_entries(filename: &str, errs: &mut error::Record) -> (Option<Vec<Ent
             return (None, crate::util::date::Interval::Empty);
         }
     };
-    let data = parse::extract(filename, errs, &contents);
+    read_source(filename, errs, &contents, conv)
+}
+
+/// Same as `read_entries` for contents that are already in memory
+///
+/// `filename` is used in error messages and to resolve imports
+pub fn read_source(
+    filename: &str,
+    errs: &mut error::Record,
+    contents: &str,
+    conv: Convention,
+) -> (Option<Vec<Entry>>, Interval<Date>) {
+    let data = parse::extract(filename, errs, contents, conv);
     if errs.is_fatal() {
         return (None, crate::util::date::Interval::Empty);
     }
     let (pairs, period) =
-        template::instanciate(filename, errs, data, std::collections::HashMap::new());
+        template::instanciate(filename, errs, data, std::collections::HashMap::new(), conv);
     if errs.is_fatal() {
         (None, period)
     } else {
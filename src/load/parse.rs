@@ -12,7 +12,7 @@ type Pair<'i> = pest::iterators::Pair<'i, Rule>;
 type Pairs<'i> = pest::iterators::Pairs<'i, Rule>;
 
 use crate::util::{
-    date::{Date, Month},
+    date::{Convention, Date, Month},
     entry::{self, Amount, Category, Entry, Span, Tag},
 };
 use crate::load::{
@@ -46,7 +46,7 @@ pub enum AstItem<'i> {
     Import(&'i str, error::Loc<'i>),
 }
 
-struct Once<'i, T> {
+pub(crate) struct Once<'i, T> {
     name: &'i str,
     hint: &'i str,
     loc: &'i error::Loc<'i>,
@@ -55,7 +55,7 @@ struct Once<'i, T> {
 }
 
 impl<'i, T> Once<'i, T> {
-    fn new(name: &'i str, hint: &'i str, loc: &'i error::Loc) -> Self {
+    pub(crate) fn new(name: &'i str, hint: &'i str, loc: &'i error::Loc) -> Self {
         Self {
             name,
             hint,
@@ -65,8 +65,9 @@ impl<'i, T> Once<'i, T> {
         }
     }
 
-    fn try_set(&mut self, val: T, errs: &mut error::Record) {
-        if self.data.is_some() {
+    pub(crate) fn try_set(&mut self, val: T, errs: &mut error::Record) {
+        // a field defined more than twice is reported once
+        if self.data.is_some() && self.valid {
             errs.make("Duplicate field definition")
                 .span(self.loc, format!("attempt to override {}", self.name))
                 .text("Each field may only be defined once")
@@ -76,7 +77,7 @@ impl<'i, T> Once<'i, T> {
         self.data = Some(val);
     }
 
-    fn try_get(self, errs: &mut error::Record) -> Option<T> {
+    pub(crate) fn try_get(self, errs: &mut error::Record) -> Option<T> {
         if self.valid {
             if self.data.is_none() {
                 errs.make("Missing field definition")
@@ -104,9 +105,9 @@ impl<'i, T> Once<'i, T> {
 ///
 /// Caller should determine the success of this function not through its return value
 /// but by querying `errs` (e.g. by checking `errs.is_fatal()` or `errs.count_errors()`)
-pub fn extract<'i>(path: &'i str, errs: &mut error::Record, contents: &'i str) -> Ast<'i> {
+pub fn extract<'i>(path: &'i str, errs: &mut error::Record, contents: &'i str, conv: Convention) -> Ast<'i> {
     match BilligParser::parse(Rule::program, contents) {
-        Ok(contents) => validate(path, errs, contents),
+        Ok(contents) => validate(path, errs, contents, conv),
         Err(e) => {
             errs.make("Parsing failure").from(e.with_path(path));
             Vec::new()
@@ -117,7 +118,7 @@ pub fn extract<'i>(path: &'i str, errs: &mut error::Record, contents: &'i str) -
 // extract contents of wrapper rule
 macro_rules! subrule {
     ( $node:expr ) => {{
-        let mut items = $node.into_inner().into_iter();
+        let mut items = $node.into_inner();
         let fst = items.next().unwrap_or_else(|| panic!("No subrule"));
         if items.next().is_some() {
             panic!("Several subrules");
@@ -129,7 +130,7 @@ macro_rules! subrule {
 // get first and rest of inner
 macro_rules! decapitate {
     ( $node:expr ) => {{
-        let mut items = $node.into_inner().into_iter();
+        let mut items = $node.into_inner();
         let fst = items.next().unwrap_or_else(|| panic!("No head"));
         (fst, items)
     }};
@@ -138,7 +139,7 @@ macro_rules! decapitate {
 // extract two-element inner
 macro_rules! pair {
     ( $node:expr ) => {{
-        let mut items = $node.into_inner().into_iter();
+        let mut items = $node.into_inner();
         let fst = items.next().unwrap_or_else(|| panic!("No 1st"));
         let snd = items.next().unwrap_or_else(|| panic!("No 2nd"));
         assert!(items.next().is_none());
@@ -149,7 +150,7 @@ macro_rules! pair {
 // extract three-element inner
 macro_rules! triplet {
     ( $node:expr ) => {{
-        let mut items = $node.into_inner().into_iter();
+        let mut items = $node.into_inner();
         let fst = items.next().unwrap_or_else(|| panic!("No 1st"));
         let snd = items.next().unwrap_or_else(|| panic!("No 2nd"));
         let thr = items.next().unwrap_or_else(|| panic!("No 3rd"));
@@ -177,7 +178,7 @@ macro_rules! parse_amount {
 ///
 /// Sequentially validates each entry or template, records errors, accumulates the
 /// correct ones into the return value.
-pub fn validate<'i>(path: &'i str, errs: &mut error::Record, pairs: Pairs<'i>) -> Ast<'i> {
+pub fn validate<'i>(path: &'i str, errs: &mut error::Record, pairs: Pairs<'i>, conv: Convention) -> Ast<'i> {
     let mut ast = Vec::new();
     'pairs: for pair in pairs {
         match pair.as_rule() {
@@ -192,7 +193,7 @@ pub fn validate<'i>(path: &'i str, errs: &mut error::Record, pairs: Pairs<'i>) -
                 let (head, body) = decapitate!(pair);
                 assert_eq!(head.as_rule(), Rule::marker_year);
                 let year = parse_usize!(head);
-                let items = validate_year(path, errs, year, body.collect::<Vec<_>>());
+                let items = validate_year(path, errs, year, body.collect::<Vec<_>>(), conv);
                 for item in items {
                     ast.push(item);
                 }
@@ -241,7 +242,7 @@ fn validate_template<'i>(
                         .span(&loc, "provided here")
                         .text("This keyword is not recognized")
                         .hint("maybe you meant one of Food, Com, Mov, Home, ...")
-                        .hint("or maybe try Day, Week, Month, Year");
+                        .hint("or maybe try Day, Week, Month, Quarter, Semester, Year");
                     return None;
                 }
             }
@@ -279,7 +280,7 @@ fn validate_template<'i>(
 /// Parse list of arguments
 ///
 /// Grammar ensures this cannot fail
-fn read_args(pairs: Pairs) -> (Vec<&str>, Vec<(&str, Arg)>) {
+fn read_args(pairs: Pairs<'_>) -> (Vec<&str>, Vec<(&str, Arg<'_>)>) {
     let mut positional = Vec::new();
     let mut named = Vec::new();
     for pair in pairs {
@@ -294,7 +295,7 @@ fn read_args(pairs: Pairs) -> (Vec<&str>, Vec<(&str, Arg)>) {
 /// Parse a single positional or named argument
 ///
 /// Grammar ensures this cannot fail
-fn read_arg(pair: Pair) -> (&str, Option<Arg>) {
+fn read_arg(pair: Pair<'_>) -> (&str, Option<Arg<'_>>) {
     match pair.as_rule() {
         Rule::template_positional_arg => {
             let name = pair.as_str();
@@ -338,7 +339,7 @@ fn read_template_amount(pair: Pair) -> models::amount::Template {
     };
     let items = match pair.as_rule() {
         Rule::money_amount => vec![pair],
-        _ => pair.into_inner().into_iter().collect::<Vec<_>>(),
+        _ => pair.into_inner().collect::<Vec<_>>(),
     };
     use models::amount::*;
     let mut sum = Template::new(sign);
@@ -355,7 +356,7 @@ fn read_template_amount(pair: Pair) -> models::amount::Template {
 }
 
 /// Parse an expense category
-fn validate_cat(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Category> {
+pub(crate) fn validate_cat(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Category> {
     let loc = (path, pair.as_span().clone());
     match pair.as_str().parse::<entry::Category>() {
         Ok(category) => Some(category),
@@ -370,8 +371,8 @@ fn validate_cat(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Cate
 }
 
 /// Parse a span (length, window, count)
-fn validate_span(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Span> {
-    let mut pair = pair.into_inner().into_iter().peekable();
+pub(crate) fn validate_span(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Span> {
+    let mut pair = pair.into_inner().peekable();
     let item_dur = pair.next().unwrap();
     let loc = (path, item_dur.as_span().clone());
     let duration = match item_dur.as_str().parse::<entry::Duration>() {
@@ -380,7 +381,7 @@ fn validate_span(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Spa
             errs.make("Invalid duration")
                 .span(&loc, "provided here")
                 .text(format!("'{}' is not a valid duration", item_dur.as_str()))
-                .hint("use one of Day, Week, Month, Year");
+                .hint("use one of Day, Week, Month, Quarter, Semester, Year");
             return None;
         }
     };
@@ -414,7 +415,7 @@ fn validate_span(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Spa
 /// arguments are valid identifiers, and builtin placeholders (`@Day`, `@Date`, ...)
 /// have keyword status
 fn read_template_tag(pair: Pair) -> models::tag::Template {
-    let concat = pair.into_inner().into_iter().collect::<Vec<_>>();
+    let concat = pair.into_inner().collect::<Vec<_>>();
     use models::tag::*;
     let mut strs = Template::new();
     for item in concat {
@@ -443,6 +444,7 @@ fn validate_year<'i>(
     errs: &mut error::Record,
     year: usize,
     pairs: Vec<Pair<'i>>,
+    conv: Convention,
 ) -> Vec<AstItem<'i>> {
     let mut v = Vec::new();
     for pair in pairs {
@@ -458,7 +460,7 @@ fn validate_year<'i>(
                 continue;
             }
         };
-        let items = validate_month(path, errs, year, month, rest.collect::<Vec<_>>());
+        let items = validate_month(path, errs, year, month, rest.collect::<Vec<_>>(), conv);
         for item in items {
             v.push(item);
         }
@@ -476,6 +478,7 @@ fn validate_month<'i>(
     year: usize,
     month: Month,
     pairs: Vec<Pair<'i>>,
+    conv: Convention,
 ) -> Vec<AstItem<'i>> {
     let mut v = Vec::new();
     'pairs: for pair in pairs {
@@ -485,7 +488,7 @@ fn validate_month<'i>(
         let day = parse_usize!(day);
         match Date::from(year, month, day) {
             Ok(date) => {
-                let items = validate_day(path, errs, date, rest.collect::<Vec<_>>());
+                let items = validate_day(path, errs, date, rest.collect::<Vec<_>>(), conv);
                 for item in items {
                     v.push(item);
                 }
@@ -511,6 +514,7 @@ fn validate_day<'i>(
     errs: &mut error::Record,
     date: Date,
     pairs: Vec<Pair<'i>>,
+    conv: Convention,
 ) -> Vec<AstItem<'i>> {
     let mut v = Vec::new();
     'pairs: for pair in pairs {
@@ -522,7 +526,7 @@ fn validate_day<'i>(
                 v.push(AstItem::Instance(date, res));
             }
             Rule::plain_entry => {
-                let res = match validate_plain_entry(path, errs, date, entry) {
+                let res = match validate_plain_entry(path, errs, date, entry, conv) {
                     Some(x) => x,
                     None => continue 'pairs,
                 };
@@ -583,6 +587,7 @@ fn validate_plain_entry(
     errs: &mut error::Record,
     date: Date,
     pair: Pair,
+    conv: Convention,
 ) -> Option<Entry> {
     let loc = (path, pair.as_span().clone());
     let mut value = Once::new("val", "42.69", &loc);
@@ -596,7 +601,7 @@ fn validate_plain_entry(
                     cat.try_set(c, errs);
                 } else if let Ok(d) = item.as_str().parse::<entry::Duration>() {
                     span.try_set(
-                        Span::from(d, entry::Window::Posterior, 1).period(date),
+                        Span::from(d, entry::Window::Posterior, 1).period(date, conv),
                         errs,
                     );
                 } else {
@@ -604,7 +609,7 @@ fn validate_plain_entry(
                         .span(&loc, "provided here")
                         .text("This keyword is not recognized")
                         .hint("maybe you meant one of Food, Com, Mov, Home, ...")
-                        .hint("or maybe try Day, Week, Month, Year");
+                        .hint("or maybe try Day, Week, Month, Quarter, Semester, Year");
                     return None;
                 }
             }
@@ -615,7 +620,7 @@ fn validate_plain_entry(
                 cat.try_set(validate_cat(path, errs, item)?, errs);
             }
             Rule::span_value => {
-                span.try_set(validate_span(path, errs, item)?.period(date), errs);
+                span.try_set(validate_span(path, errs, item)?.period(date, conv), errs);
             }
             Rule::string => {
                 tag.try_set(Tag(item.as_str().to_string()), errs);
@@ -0,0 +1,305 @@
+//! Categorisation rules for imported bank statements
+//!
+//! A rules file uses the syntax of plain entries, without amounts or dates:
+//!
+//! ```txt
+//! profile { date 0 "%d/%m/%Y", description 1, amount 2, delimiter ";" }
+//!
+//! "SNCF" => Mov, span Day;
+//! "LOYER" => Home, Month<Curr>, "Rent";
+//! "" => Fun; // matches everything
+//! ```
+//!
+//! Patterns are regular expressions tried in order against the description
+//! of each transaction, the first one that matches determines the category,
+//! span (`Day` by default) and tag (the description by default) of the entry.
+
+use pest::Parser;
+use regex::Regex;
+
+use crate::load::{
+    error,
+    parse::{self, BilligParser, Once, Rule},
+};
+use crate::util::entry::{self, Category, Span};
+
+/// Wrapper around Pest's `Pair`
+type Pair<'i> = pest::iterators::Pair<'i, Rule>;
+
+/// Contents of a rules file
+#[derive(Debug)]
+pub struct Rules {
+    pub profile: Profile,
+    patterns: Vec<Pattern>,
+}
+
+/// Layout of a CSV statement
+#[derive(Debug, Clone)]
+pub struct Profile {
+    /// column of the date and its format (as understood by `chrono`)
+    pub date: (usize, String),
+    pub description: usize,
+    pub amount: Amounts,
+    pub delimiter: u8,
+    /// decimal separator of amounts
+    pub decimal: char,
+    /// number of lines to skip at the start of the file
+    pub header: usize,
+    /// format of the dates of QIF statements if they are not in US format
+    pub qif_date: Option<String>,
+}
+
+/// Columns that hold the amount of a transaction
+#[derive(Debug, Clone, Copy)]
+pub enum Amounts {
+    /// a single signed amount
+    Signed(usize),
+    /// expenses and incomes in separate columns, both positive
+    Split { debit: usize, credit: usize },
+}
+
+/// What to do with the transactions whose description matches `regex`
+#[derive(Debug)]
+pub struct Pattern {
+    regex: Regex,
+    pub cat: Category,
+    pub span: Span,
+    pub tag: Option<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            date: (0, String::from("%Y-%m-%d")),
+            description: 1,
+            amount: Amounts::Signed(2),
+            delimiter: b',',
+            decimal: '.',
+            header: 1,
+            qif_date: None,
+        }
+    }
+}
+
+impl Rules {
+    /// Read the rules file at `path`
+    pub fn load(path: &str, errs: &mut error::Record) -> Option<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                errs.make("File not found")
+                    .text(format!("Rules file is '{}'", path))
+                    .hint("check that the path is correct");
+                return None;
+            }
+        };
+        Self::extract(path, errs, &contents)
+    }
+
+    /// Parse rules from `contents`
+    pub fn extract(path: &str, errs: &mut error::Record, contents: &str) -> Option<Self> {
+        let pairs = match BilligParser::parse(Rule::rules_program, contents) {
+            Ok(pairs) => pairs,
+            Err(e) => {
+                errs.make("Parsing failure").from(e.with_path(path));
+                return None;
+            }
+        };
+        let mut profile = None;
+        let mut patterns = Vec::new();
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::profile => {
+                    let loc = (path, pair.as_span());
+                    if profile.is_some() {
+                        errs.make("Duplicate profile")
+                            .span(&loc, "second definition")
+                            .hint("merge the two profiles");
+                    }
+                    profile = Some(validate_profile(path, errs, pair)?);
+                }
+                Rule::rule => patterns.push(validate_pattern(path, errs, pair)?),
+                Rule::EOI => break,
+                _ => unreachable!(),
+            }
+        }
+        if errs.is_fatal() {
+            return None;
+        }
+        Some(Self {
+            profile: profile.unwrap_or_default(),
+            patterns,
+        })
+    }
+
+    /// First pattern that matches `description`
+    pub fn categorize(&self, description: &str) -> Option<&Pattern> {
+        self.patterns.iter().find(|p| p.regex.is_match(description))
+    }
+}
+
+fn validate_profile(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Profile> {
+    let mut profile = Profile::default();
+    let mut debit = None;
+    let mut credit = None;
+    for field in pair.into_inner() {
+        let loc = (path, field.as_span());
+        let mut inner = field.into_inner();
+        let name = inner.next().unwrap().as_str();
+        let args = inner.collect::<Vec<_>>();
+        let column = |i: usize| {
+            args.get(i)
+                .filter(|a| a.as_rule() == Rule::number)
+                .map(|a| a.as_str().parse::<usize>().unwrap())
+        };
+        let text = |i: usize| {
+            args.get(i)
+                .filter(|a| a.as_rule() == Rule::string)
+                .map(|a| a.as_str().to_string())
+        };
+        let ok = match (name, args.len()) {
+            ("date", 1) => column(0).map(|c| profile.date.0 = c),
+            ("date", 2) => column(0).and_then(|c| text(1).map(|f| profile.date = (c, f))),
+            ("description", 1) => column(0).map(|c| profile.description = c),
+            ("amount", 1) => column(0).map(|c| profile.amount = Amounts::Signed(c)),
+            ("debit", 1) => column(0).map(|c| debit = Some(c)),
+            ("credit", 1) => column(0).map(|c| credit = Some(c)),
+            ("header", 1) => column(0).map(|c| profile.header = c),
+            ("qif_date", 1) => text(0).map(|f| profile.qif_date = Some(f)),
+            ("delimiter", 1) => text(0)
+                .filter(|t| t.len() == 1)
+                .map(|t| profile.delimiter = t.as_bytes()[0]),
+            ("decimal", 1) => text(0)
+                .filter(|t| t == "." || t == ",")
+                .map(|t| profile.decimal = t.chars().next().unwrap()),
+            _ => None,
+        };
+        if ok.is_none() {
+            errs.make("Invalid profile field")
+                .span(&loc, "provided here")
+                .text(format!("'{}' is not a valid field or has invalid arguments", name))
+                .hint("expected one of date N \"FORMAT\", description N, amount N, debit N, credit N")
+                .hint("or header N, delimiter \"C\", decimal \".\", qif_date \"FORMAT\"");
+            return None;
+        }
+    }
+    match (debit, credit) {
+        (Some(debit), Some(credit)) => profile.amount = Amounts::Split { debit, credit },
+        (None, None) => (),
+        _ => {
+            errs.make("Incomplete profile")
+                .text("'debit' and 'credit' must be given together")
+                .hint("use 'amount' if the statement has a single column of signed amounts");
+            return None;
+        }
+    }
+    Some(profile)
+}
+
+fn validate_pattern(path: &str, errs: &mut error::Record, pair: Pair) -> Option<Pattern> {
+    let loc = (path, pair.as_span());
+    let mut inner = pair.into_inner();
+    let pattern = inner.next().unwrap();
+    let regex = match Regex::new(pattern.as_str()) {
+        Ok(regex) => regex,
+        Err(e) => {
+            errs.make("Invalid pattern")
+                .span(&(path, pattern.as_span()), "provided here")
+                .text(e.to_string().lines().last().unwrap_or_default().to_string())
+                .hint("patterns are regular expressions, escape special characters with '\\'");
+            return None;
+        }
+    };
+    let mut cat = Once::new("type", "Food", &loc);
+    let mut span = Once::new("span", "Week<Post> 2", &loc);
+    let mut tag = Once::new("tag", "Some information", &loc);
+    for item in inner.next().unwrap().into_inner() {
+        match item.as_rule() {
+            Rule::builtin => {
+                if let Ok(c) = item.as_str().parse::<Category>() {
+                    cat.try_set(c, errs);
+                } else if let Ok(d) = item.as_str().parse::<entry::Duration>() {
+                    span.try_set(Span::from(d, entry::Window::Posterior, 1), errs);
+                } else {
+                    errs.make("Invalid builtin of ambiguous nature")
+                        .span(&(path, item.as_span()), "provided here")
+                        .text("This keyword is not recognized")
+                        .hint("maybe you meant one of Food, Com, Mov, Home, ...")
+                        .hint("or maybe try Day, Week, Month, Quarter, Semester, Year");
+                    return None;
+                }
+            }
+            Rule::expense_type => cat.try_set(parse::validate_cat(path, errs, item)?, errs),
+            Rule::span_value => span.try_set(parse::validate_span(path, errs, item)?, errs),
+            Rule::string => tag.try_set(item.as_str().to_string(), errs),
+            _ => {
+                errs.make("Invalid field in rule")
+                    .span(&(path, item.as_span()), "provided here")
+                    .text("Amounts and periods are read from the statement")
+                    .hint("only type, span and tag can be given");
+                return None;
+            }
+        }
+    }
+    let cat = cat.try_get(errs)?;
+    // span and tag are optional
+    let span = span.try_get(&mut error::Record::new());
+    let tag = tag.try_get(&mut error::Record::new());
+    Some(Pattern {
+        regex,
+        cat,
+        span: span.unwrap_or_else(|| Span::from(entry::Duration::Day, entry::Window::Posterior, 1)),
+        tag,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn categorize() {
+        let mut errs = error::Record::new();
+        let src = r#"
+            profile { date 0 "%d/%m/%Y", debit 2, credit 3, delimiter ";", decimal "," }
+            "SNCF" => Mov, Month<Post>;
+            "(?i)carrefour|lidl" => type Food, tag "Groceries";
+            "" => Fun;
+        "#;
+        let rules = Rules::extract("test", &mut errs, src).unwrap();
+        assert_eq!(rules.profile.delimiter, b';');
+        assert!(matches!(rules.profile.amount, Amounts::Split { debit: 2, credit: 3 }));
+        let p = rules.categorize("PRLV SNCF VOYAGES").unwrap();
+        assert_eq!(p.cat, Category::Movement);
+        assert_eq!(p.span.to_string(), "Month<Post> 1");
+        let p = rules.categorize("Lidl 0412").unwrap();
+        assert_eq!(p.tag.as_deref(), Some("Groceries"));
+        assert_eq!(rules.categorize("anything").unwrap().cat, Category::Fun);
+    }
+
+    #[test]
+    fn example() {
+        let mut errs = error::Record::new();
+        let rules = Rules::load("examples/import/rules.bil", &mut errs).unwrap();
+        let trs = crate::import::csv::read("examples/import/bank.csv", &mut errs, &rules.profile).unwrap();
+        assert_eq!(trs.len(), 6);
+        let candidates = crate::import::categorize(&mut errs, &rules, trs);
+        assert_eq!(candidates.len(), 6);
+        assert_eq!(errs.count_warnings(), 0);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        for src in &[
+            "\"x\" => 5, Food;",
+            "\"x\" => Day;",
+            "\"(\" => Food;",
+            "profile { debit 1 }",
+            "profile { date \"x\" }",
+        ] {
+            let mut errs = error::Record::new();
+            assert!(Rules::extract("test", &mut errs, src).is_none(), "{}", src);
+            assert!(errs.is_fatal());
+        }
+    }
+}
@@ -0,0 +1,843 @@
+//! Lossless syntax tree of a `.bil` file
+//!
+//! Where `parse::Ast` only keeps what is needed to build entries,
+//! a `Document` remembers the structure of the file (year/month/day blocks,
+//! keywords, template definitions) along with its comments, so that it
+//! can be printed back in canonical form (see `cli::format`).
+//!
+//! Comments are not part of the grammar: they live in the gaps between
+//! the spans of the nodes and are attached to the closest node as either
+//! leading comments (on the lines before it) or trailing comments
+//! (on the same line or on the lines directly after it, at least as indented).
+
+use pest::Parser;
+
+use crate::load::{
+    error,
+    parse::{BilligParser, Rule},
+};
+use crate::util::{
+    date::{Date, Month},
+    entry::{Category, Duration},
+};
+
+/// Wrapper around Pest's `Pair`
+type Pair<'i> = pest::iterators::Pair<'i, Rule>;
+
+/// Index of the comments of a node in `Document::trivia`
+pub type Id = usize;
+
+/// A whole file
+#[derive(Debug)]
+pub struct Document<'i> {
+    pub items: Vec<Item<'i>>,
+    src: &'i str,
+    /// comments of each node
+    trivia: Vec<Trivia<'i>>,
+    /// position of each node in `src`
+    extents: Vec<Extent>,
+    /// comments at the end of the file that are not attached to any node
+    pub dangling: Id,
+    /// text after an explicit `EOF` marker, kept verbatim
+    pub tail: Option<&'i str>,
+}
+
+/// Comments attached to a node
+#[derive(Debug, Default, Clone)]
+pub struct Trivia<'i> {
+    /// full-line comments before the node
+    pub leading: Vec<&'i str>,
+    /// comment that ends the line of the node
+    pub same_line: Option<&'i str>,
+    /// full-line comments directly after the node
+    pub trailing: Vec<&'i str>,
+}
+
+/// Region of the source covered by a node
+#[derive(Debug, Clone, Copy)]
+struct Extent {
+    /// start of the first leading comment
+    start: usize,
+    /// start of the node itself
+    mark: usize,
+    /// end of the node or of its last trailing comment
+    end: usize,
+}
+
+/// Each toplevel item of the file
+#[derive(Debug)]
+pub enum Item<'i> {
+    Template(Template<'i>),
+    Year(Year<'i>),
+    /// an external file import and its path
+    Import(Id, &'i str),
+}
+
+/// A `YYYY:` block
+#[derive(Debug)]
+pub struct Year<'i> {
+    pub id: Id,
+    pub year: u16,
+    pub months: Vec<MonthBlock<'i>>,
+}
+
+/// A `Mmm:` block
+#[derive(Debug)]
+pub struct MonthBlock<'i> {
+    pub id: Id,
+    pub month: Month,
+    pub days: Vec<Day<'i>>,
+}
+
+/// A `DD:` block
+#[derive(Debug)]
+pub struct Day<'i> {
+    pub id: Id,
+    pub day: u8,
+    pub entries: Vec<Entry<'i>>,
+}
+
+/// A single entry terminated by `;`
+#[derive(Debug)]
+pub struct Entry<'i> {
+    pub id: Id,
+    pub kind: EntryKind<'i>,
+}
+
+#[derive(Debug)]
+pub enum EntryKind<'i> {
+    /// a sequence of fields
+    Plain(Vec<Field<'i>>),
+    /// `!name positional named=value`
+    Expand(Expand<'i>),
+}
+
+/// A template instanciation
+#[derive(Debug)]
+pub struct Expand<'i> {
+    pub name: &'i str,
+    pub positional: Vec<Value<'i>>,
+    pub named: Vec<(&'i str, Value<'i>)>,
+}
+
+/// A template definition
+#[derive(Debug)]
+pub struct Template<'i> {
+    /// `!name args {`
+    pub id: Id,
+    pub name: &'i str,
+    pub positional: Vec<&'i str>,
+    pub named: Vec<(&'i str, Value<'i>)>,
+    pub fields: Vec<(Id, Field<'i>)>,
+    /// the closing brace
+    pub end: Id,
+}
+
+/// An argument or a default value
+#[derive(Debug, Clone, Copy)]
+pub enum Value<'i> {
+    Amount(&'i str),
+    Tag(&'i str),
+}
+
+/// A field of an entry or of a template
+#[derive(Debug)]
+pub enum Field<'i> {
+    /// `val`, possibly negated and summed in templates
+    Val { neg: bool, terms: Vec<Term<'i>> },
+    /// `type`
+    Type(&'i str),
+    /// `span` with optional window and count
+    Span {
+        duration: &'i str,
+        window: Option<&'i str>,
+        count: Option<&'i str>,
+    },
+    /// `tag`, possibly concatenated in templates
+    Tag(Vec<Term<'i>>),
+    /// `period`
+    Period(&'i str),
+}
+
+/// Atoms of `val` and `tag` fields
+#[derive(Debug, Clone, Copy)]
+pub enum Term<'i> {
+    /// a monetary value
+    Amount(&'i str),
+    /// a string literal
+    Text(&'i str),
+    /// `*name`
+    Arg(&'i str),
+    /// one of `@Day`, `@Month`, `@Year`, `@Date`, `@Weekday`
+    Time(&'i str),
+}
+
+/// Accumulates the nodes of a document in order of appearance
+struct Builder<'i> {
+    src: &'i str,
+    /// spans of the nodes and whether to look for comments inside of them
+    marks: Vec<(usize, usize, bool)>,
+}
+
+impl Builder<'_> {
+    fn mark(&mut self, start: usize, end: usize, inside: bool) -> Id {
+        self.marks.push((start, end, inside));
+        self.marks.len() - 1
+    }
+
+    fn mark_pair(&mut self, pair: &Pair) -> Id {
+        let span = pair.as_span();
+        self.mark(span.start(), span.end(), true)
+    }
+
+    /// Mark a node followed by its terminator (`:` or `;`)
+    fn mark_terminated(&mut self, pair: &Pair) -> Id {
+        let span = pair.as_span();
+        let end = span.end() + skip_comments(&self.src[span.end()..]) + 1;
+        self.mark(span.start(), end, true)
+    }
+}
+
+/// Build the syntax tree of `contents`
+///
+/// As for `parse::extract`, failures are recorded in `errs`, but only
+/// syntax errors and invalid month names are detected.
+pub fn extract<'i>(path: &'i str, errs: &mut error::Record, contents: &'i str) -> Option<Document<'i>> {
+    let pairs = match BilligParser::parse(Rule::program, contents) {
+        Ok(pairs) => pairs,
+        Err(e) => {
+            errs.make("Parsing failure").from(e.with_path(path));
+            return None;
+        }
+    };
+    let mut builder = Builder {
+        src: contents,
+        marks: Vec::new(),
+    };
+    let mut items = Vec::new();
+    let mut end = None;
+    let mut last = 0;
+    for pair in pairs {
+        if pair.as_rule() != Rule::EOI {
+            last = pair.as_span().end();
+        }
+        match pair.as_rule() {
+            Rule::template_descriptor => items.push(Item::Template(read_template(&mut builder, pair))),
+            Rule::entries_year => items.push(Item::Year(read_year(path, errs, &mut builder, pair)?)),
+            Rule::import => {
+                let id = builder.mark_pair(&pair);
+                items.push(Item::Import(id, pair.into_inner().as_str()));
+            }
+            Rule::EOI => end = Some(pair.as_span().start()),
+            _ => unreachable!(),
+        }
+    }
+    // without an EOI the file ends with an explicit EOF marker, which is
+    // the first thing that is not a comment after the last node
+    let (end, tail) = match end {
+        Some(end) => (end, None),
+        None => {
+            let pos = last + skip_comments(&contents[last..]);
+            (pos, Some(&contents[pos..]))
+        }
+    };
+    let dangling = builder.mark(end, end, false);
+    let (trivia, extents) = attach_comments(contents, &builder.marks);
+    Some(Document {
+        items,
+        src: contents,
+        trivia,
+        extents,
+        dangling,
+        tail,
+    })
+}
+
+fn read_template<'i>(b: &mut Builder<'i>, pair: Pair<'i>) -> Template<'i> {
+    let start = pair.as_span().start();
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap();
+    let args = inner.next().unwrap();
+    let body = inner.next().unwrap();
+    let id = b.mark(start, args.as_span().end(), true);
+    let mut positional = Vec::new();
+    let mut named = Vec::new();
+    for arg in args.into_inner() {
+        match arg.as_rule() {
+            Rule::template_positional_arg => positional.push(arg.as_str()),
+            Rule::template_named_arg => {
+                let mut arg = arg.into_inner();
+                let name = arg.next().unwrap().as_str();
+                named.push((name, read_value(arg.next().unwrap())));
+            }
+            _ => unreachable!(),
+        }
+    }
+    let body_end = body.as_span().end();
+    let mut fields = Vec::new();
+    for sub in body.into_inner() {
+        // the span of a bare string does not include its quotes
+        let id = if sub.as_rule() == Rule::string {
+            let span = sub.as_span();
+            b.mark(span.start() - 1, span.end() + 1, false)
+        } else {
+            b.mark_pair(&sub)
+        };
+        let field = match sub.as_rule() {
+            Rule::template_money_amount => {
+                let sub = sub.into_inner().next().unwrap();
+                let (neg, sub) = match sub.as_rule() {
+                    Rule::builtin_neg => (true, sub.into_inner().next().unwrap()),
+                    _ => (false, sub),
+                };
+                Field::Val {
+                    neg,
+                    terms: sub.into_inner().map(read_term).collect(),
+                }
+            }
+            Rule::template_tag => Field::Tag(sub.into_inner().next().unwrap().into_inner().map(read_term).collect()),
+            _ => read_field(sub),
+        };
+        fields.push((id, field));
+    }
+    let end = b.mark(body_end - 1, body_end, false);
+    Template {
+        id,
+        name: name.as_str(),
+        positional,
+        named,
+        fields,
+        end,
+    }
+}
+
+fn read_year<'i>(path: &str, errs: &mut error::Record, b: &mut Builder<'i>, pair: Pair<'i>) -> Option<Year<'i>> {
+    let mut inner = pair.into_inner();
+    let marker = inner.next().unwrap();
+    let id = b.mark_terminated(&marker);
+    let mut months = Vec::new();
+    for month in inner {
+        let mut inner = month.into_inner();
+        let marker = inner.next().unwrap();
+        let id = b.mark_terminated(&marker);
+        let month = match marker.as_str().parse::<Month>() {
+            Ok(month) => month,
+            Err(()) => {
+                errs.make("Invalid month")
+                    .span(&(path, marker.as_span()), "provided here")
+                    .hint("Months are 'Jan', 'Feb', ..., 'Dec'");
+                return None;
+            }
+        };
+        let mut days = Vec::new();
+        for day in inner {
+            let mut inner = day.into_inner();
+            let marker = inner.next().unwrap();
+            let id = b.mark_terminated(&marker);
+            let entries = inner
+                .map(|entry| {
+                    let entry = entry.into_inner().next().unwrap();
+                    let id = b.mark_terminated(&entry);
+                    let kind = match entry.as_rule() {
+                        Rule::plain_entry => EntryKind::Plain(entry.into_inner().map(read_field).collect()),
+                        Rule::expand_entry => EntryKind::Expand(read_expand(entry)),
+                        _ => unreachable!(),
+                    };
+                    Entry { id, kind }
+                })
+                .collect();
+            days.push(Day {
+                id,
+                day: marker.as_str().parse().unwrap(),
+                entries,
+            });
+        }
+        months.push(MonthBlock { id, month, days });
+    }
+    Some(Year {
+        id,
+        year: marker.as_str().parse().unwrap(),
+        months,
+    })
+}
+
+fn read_expand(pair: Pair) -> Expand {
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap().as_str();
+    let mut positional = Vec::new();
+    let mut named = Vec::new();
+    for arg in inner.flat_map(|args| args.into_inner()) {
+        match arg.as_rule() {
+            Rule::named_arg => {
+                let mut arg = arg.into_inner();
+                let name = arg.next().unwrap().as_str();
+                named.push((name, read_value(arg.next().unwrap())));
+            }
+            _ => positional.push(read_value(arg)),
+        }
+    }
+    Expand {
+        name,
+        positional,
+        named,
+    }
+}
+
+fn read_value(pair: Pair) -> Value {
+    match pair.as_rule() {
+        Rule::money_amount => Value::Amount(pair.as_str()),
+        Rule::string => Value::Tag(pair.as_str()),
+        _ => unreachable!(),
+    }
+}
+
+fn read_term(pair: Pair) -> Term {
+    match pair.as_rule() {
+        Rule::money_amount => Term::Amount(pair.as_str()),
+        Rule::string => Term::Text(pair.as_str()),
+        Rule::identifier => Term::Arg(pair.as_str()),
+        Rule::template_time => Term::Time(pair.as_str()),
+        _ => unreachable!("{:?}", pair),
+    }
+}
+
+/// Read a field that has the same syntax in entries and in templates
+fn read_field(pair: Pair) -> Field {
+    match pair.as_rule() {
+        Rule::money_amount => Field::Val {
+            neg: false,
+            terms: vec![Term::Amount(pair.as_str())],
+        },
+        Rule::expense_type => Field::Type(pair.as_str()),
+        Rule::string => Field::Tag(vec![Term::Text(pair.as_str())]),
+        Rule::period => Field::Period(pair.as_str()),
+        Rule::span_value => {
+            let mut inner = pair.into_inner();
+            let duration = inner.next().unwrap().as_str();
+            let mut window = None;
+            let mut count = None;
+            for item in inner {
+                match item.as_rule() {
+                    Rule::window => window = Some(item.as_str()),
+                    _ => count = Some(item.as_str()),
+                }
+            }
+            Field::Span {
+                duration,
+                window,
+                count,
+            }
+        }
+        Rule::builtin => {
+            let s = pair.as_str();
+            if s.parse::<Category>().is_ok() || s.parse::<Duration>().is_err() {
+                Field::Type(s)
+            } else {
+                Field::Span {
+                    duration: s,
+                    window: None,
+                    count: None,
+                }
+            }
+        }
+        _ => unreachable!("{:?}", pair),
+    }
+}
+
+impl<'i> Document<'i> {
+    /// Comments attached to a node
+    pub fn trivia(&self, id: Id) -> &Trivia<'i> {
+        &self.trivia[id]
+    }
+
+    /// Sort blocks by date
+    ///
+    /// Year blocks are merged if they are for the same year, as are months
+    /// of the same year and days of the same month. Entries of the same day
+    /// keep their relative order. Year blocks separated by a template
+    /// definition or an import are never reordered since that could change
+    /// which templates are in scope.
+    pub fn sort(&mut self) {
+        let mut items = Vec::new();
+        let mut years = Vec::new();
+        for item in std::mem::take(&mut self.items) {
+            match item {
+                Item::Year(year) => years.push(year),
+                other => {
+                    self.flush_years(&mut years, &mut items);
+                    items.push(other);
+                }
+            }
+        }
+        self.flush_years(&mut years, &mut items);
+        self.items = items;
+    }
+
+    /// Sort and merge a group of consecutive years
+    fn flush_years(&mut self, years: &mut Vec<Year<'i>>, items: &mut Vec<Item<'i>>) {
+        self.merge(years);
+        for mut year in years.drain(..) {
+            self.merge(&mut year.months);
+            for month in &mut year.months {
+                self.merge(&mut month.days);
+            }
+            items.push(Item::Year(year));
+        }
+    }
+
+    /// Stable sort then merge blocks with the same key
+    fn merge<B: Block<'i>>(&mut self, blocks: &mut Vec<B>) {
+        blocks.sort_by_key(|b| b.key());
+        let mut merged: Vec<B> = Vec::new();
+        for mut block in blocks.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.key() == block.key() => {
+                    let children = std::mem::take(block.children());
+                    if let Some(first) = children.first() {
+                        self.transfer(block.id(), first.id());
+                    }
+                    last.children().extend(children);
+                }
+                _ => merged.push(block),
+            }
+        }
+        *blocks = merged;
+    }
+
+    /// Move the comments of `from` to the front of the leading comments of `to`
+    fn transfer(&mut self, from: Id, to: Id) {
+        let Trivia {
+            mut leading,
+            same_line,
+            trailing,
+        } = std::mem::take(&mut self.trivia[from]);
+        leading.extend(same_line);
+        leading.extend(trailing);
+        let target = &mut self.trivia[to].leading;
+        leading.append(target);
+        *target = leading;
+    }
+}
+
+impl Document<'_> {
+    /// Source text with `entry` added on `date`
+    ///
+    /// The entry is appended to the existing block for `date` if there is one,
+    /// otherwise the missing blocks are created at their chronological position.
+    /// The rest of the text is left untouched.
+    pub fn insert(&self, date: Date, entry: &str) -> String {
+        let (y, m, d) = (date.year(), date.month(), date.day());
+        let years = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Year(year) => Some(year),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let day_text = |col: usize| format!("{}{:02}: {}", pad(col), d, entry);
+        let month_text = |col: usize| format!("{}{}:\n{}", pad(col), m, day_text(col + 4));
+        let year_text = || format!("{}:\n{}", y, month_text(4));
+        // blocks that already contain the date are preferred
+        let has_day = |month: &MonthBlock| month.days.iter().any(|day| day.day == d);
+        let has_month = |year: &Year, day: bool| {
+            year.months.iter().any(|month| month.month == m && (!day || has_day(month)))
+        };
+        let year = years
+            .iter()
+            .rev()
+            .filter(|year| year.year == y)
+            .find(|year| has_month(year, true))
+            .or_else(|| years.iter().rev().filter(|year| year.year == y).find(|year| has_month(year, false)))
+            .or_else(|| years.iter().rev().find(|year| year.year == y));
+        match year {
+            Some(year) => match year
+                .months
+                .iter()
+                .rev()
+                .filter(|month| month.month == m)
+                .find(|month| has_day(month))
+                .or_else(|| year.months.iter().rev().find(|month| month.month == m))
+            {
+                Some(month) => match month.days.iter().rev().find(|day| day.day == d) {
+                    Some(day) => {
+                        let last = day.entries.last().unwrap().id;
+                        let text = format!("{}{}", pad(self.column(last)), entry);
+                        self.insert_after(last, &text)
+                    }
+                    None => {
+                        let text = day_text(self.column(month.days[0].id));
+                        match month.days.iter().rev().find(|day| day.day < d) {
+                            Some(prev) => self.insert_after(prev.last_entry(), &text),
+                            None => self.insert_after(month.id, &text),
+                        }
+                    }
+                },
+                None => {
+                    let text = month_text(self.column(year.months[0].id));
+                    match year.months.iter().rev().find(|month| month.month < m) {
+                        Some(prev) => self.insert_after(prev.last_entry(), &text),
+                        None => self.insert_after(year.id, &text),
+                    }
+                }
+            },
+            None => match (years.iter().rev().find(|year| year.year < y), years.first()) {
+                (Some(prev), _) => self.insert_after(prev.last_entry(), &format!("\n{}", year_text())),
+                (None, Some(next)) => self.insert_before(next.id, &format!("{}\n", year_text())),
+                (None, None) if self.tail.is_some() || !self.trivia(self.dangling).leading.is_empty() => {
+                    self.insert_before(self.dangling, &format!("{}\n", year_text()))
+                }
+                (None, None) if self.src.trim().is_empty() => format!("{}\n", year_text()),
+                (None, None) => format!("{}\n\n{}\n", self.src.trim_end(), year_text()),
+            },
+        }
+    }
+
+    /// Column of the start of a node
+    fn column(&self, id: Id) -> usize {
+        let mark = self.extents[id].mark;
+        mark - self.src[..mark].rfind('\n').map(|i| i + 1).unwrap_or(0)
+    }
+
+    /// Add the lines of `text` after the line on which `id` ends
+    fn insert_after(&self, id: Id, text: &str) -> String {
+        let end = self.extents[id].end;
+        match self.src[end..].find('\n') {
+            // something else follows on the same line, break it
+            Some(len) if !self.src[end..end + len].trim().is_empty() => {
+                format!("{}\n{}{}", &self.src[..end], text, &self.src[end..])
+            }
+            Some(len) => splice(self.src, end + len + 1, text),
+            None if self.src[end..].trim().is_empty() => format!("{}\n{}\n", self.src.trim_end(), text),
+            None => format!("{}\n{}\n{}", &self.src[..end], text, &self.src[end..]),
+        }
+    }
+
+    /// Add the lines of `text` before `id` and its comments
+    fn insert_before(&self, id: Id, text: &str) -> String {
+        let start = self.extents[id].start;
+        let start = self.src[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        splice(self.src, start, text)
+    }
+}
+
+/// Insert the lines of `text` at the start of a line
+fn splice(src: &str, pos: usize, text: &str) -> String {
+    format!("{}{}\n{}", &src[..pos], text, &src[pos..])
+}
+
+fn pad(col: usize) -> String {
+    " ".repeat(col)
+}
+
+/// Blocks that contain at least one entry
+trait LastEntry {
+    fn last_entry(&self) -> Id;
+}
+
+impl LastEntry for Year<'_> {
+    fn last_entry(&self) -> Id {
+        self.months.last().unwrap().last_entry()
+    }
+}
+
+impl LastEntry for MonthBlock<'_> {
+    fn last_entry(&self) -> Id {
+        self.days.last().unwrap().last_entry()
+    }
+}
+
+impl LastEntry for Day<'_> {
+    fn last_entry(&self) -> Id {
+        self.entries.last().unwrap().id
+    }
+}
+
+/// Nodes that have comments attached
+trait Node {
+    fn id(&self) -> Id;
+}
+
+/// Date markers that can be merged
+trait Block<'i>: Node {
+    type Child: Node;
+    fn key(&self) -> u16;
+    fn children(&mut self) -> &mut Vec<Self::Child>;
+}
+
+impl Node for Entry<'_> {
+    fn id(&self) -> Id {
+        self.id
+    }
+}
+
+macro_rules! block {
+    ( $node:ident, $key:ident, $children:ident : $child:ident ) => {
+        impl Node for $node<'_> {
+            fn id(&self) -> Id {
+                self.id
+            }
+        }
+
+        impl<'i> Block<'i> for $node<'i> {
+            type Child = $child<'i>;
+            fn key(&self) -> u16 {
+                self.$key as u16
+            }
+            fn children(&mut self) -> &mut Vec<Self::Child> {
+                &mut self.$children
+            }
+        }
+    };
+}
+
+block!(Year, year, months: MonthBlock);
+block!(MonthBlock, month, days: Day);
+block!(Day, day, entries: Entry);
+
+/// Length of the prefix of `s` made only of whitespace and comments
+fn skip_comments(s: &str) -> usize {
+    let mut rest = s;
+    loop {
+        let trimmed = rest.trim_start();
+        if trimmed.starts_with("//") {
+            rest = &trimmed[trimmed.find('\n').unwrap_or(trimmed.len())..];
+        } else {
+            return s.len() - trimmed.len();
+        }
+    }
+}
+
+/// A comment found in a gap
+struct Comment<'i> {
+    text: &'i str,
+    /// position of the comment in the source
+    start: usize,
+    /// number of line breaks since the previous node or comment
+    breaks: usize,
+    /// column of the start of the comment
+    column: usize,
+}
+
+/// Find all comments in `src[start..end]`, skipping string literals
+/// if `strings` is set
+fn find_comments(src: &str, start: usize, end: usize, strings: bool) -> Vec<Comment<'_>> {
+    let mut found = Vec::new();
+    let mut breaks = 0;
+    let mut in_string = false;
+    let mut idx = start;
+    while idx < end {
+        let rest = &src[idx..end];
+        if strings && rest.starts_with('"') {
+            in_string = !in_string;
+        } else if !in_string && rest.starts_with("//") {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            let column = idx - src[..idx].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            found.push(Comment {
+                text: rest[..len].trim_end(),
+                start: idx,
+                breaks,
+                column,
+            });
+            breaks = 0;
+            idx += len;
+            continue;
+        } else if rest.starts_with('\n') {
+            breaks += 1;
+        }
+        idx += rest.chars().next().unwrap().len_utf8();
+    }
+    found
+}
+
+/// Distribute the comments of the gaps between consecutive nodes
+fn attach_comments<'i>(src: &'i str, marks: &[(usize, usize, bool)]) -> (Vec<Trivia<'i>>, Vec<Extent>) {
+    let mut trivia = vec![Trivia::default(); marks.len()];
+    let mut extents = marks
+        .iter()
+        .map(|&(start, end, _)| Extent {
+            start,
+            mark: start,
+            end,
+        })
+        .collect::<Vec<_>>();
+    let column = |pos: usize| pos - src[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let mut prev_end = 0;
+    for (i, &(start, end, inside)) in marks.iter().enumerate() {
+        let mut comments = find_comments(src, prev_end, start, false).into_iter().peekable();
+        if i > 0 {
+            let prev_column = column(marks[i - 1].0);
+            if let Some(c) = comments.peek() {
+                if c.breaks == 0 {
+                    extents[i - 1].end = c.start + c.text.len();
+                    trivia[i - 1].same_line = comments.next().map(|c| c.text);
+                }
+            }
+            while let Some(c) = comments.peek() {
+                if c.breaks == 1 && prev_column > 0 && c.column >= prev_column {
+                    extents[i - 1].end = c.start + c.text.len();
+                    trivia[i - 1].trailing.push(c.text);
+                    comments.next();
+                } else {
+                    break;
+                }
+            }
+        }
+        if let Some(c) = comments.peek() {
+            extents[i].start = c.start;
+        }
+        trivia[i].leading.extend(comments.map(|c| c.text));
+        if inside {
+            // comments inside of a node are moved after it
+            trivia[i]
+                .trailing
+                .extend(find_comments(src, start, end, true).into_iter().map(|c| c.text));
+        }
+        prev_end = end;
+    }
+    (trivia, extents)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::date::Month::*;
+
+    fn insert(src: &str, date: Date, entry: &str) -> String {
+        let mut errs = error::Record::new();
+        extract("test", &mut errs, src).unwrap().insert(date, entry)
+    }
+
+    const SRC: &str = "\
+2020:
+    Mar:
+        05: 1, Food, Day, \"a\"; // first
+        10: 2, Food, Day, \"b\";
+";
+
+    #[test]
+    fn insert_existing_day() {
+        let out = insert(SRC, Date::from(2020, Mar, 5).unwrap(), "3, Fun, Day, \"c\";");
+        assert_eq!(
+            out,
+            SRC.replace("// first\n", "// first\n            3, Fun, Day, \"c\";\n")
+        );
+    }
+
+    #[test]
+    fn insert_new_blocks() {
+        let date = |y, m, d| Date::from(y, m, d).unwrap();
+        let out = insert(SRC, date(2020, Mar, 1), "e;");
+        assert_eq!(out, SRC.replace("    Mar:\n", "    Mar:\n        01: e;\n"));
+        let out = insert(SRC, date(2020, Mar, 7), "e;");
+        assert_eq!(out, SRC.replace("// first\n", "// first\n        07: e;\n"));
+        let out = insert(SRC, date(2020, Feb, 7), "e;");
+        assert_eq!(out, SRC.replace("2020:\n", "2020:\n    Feb:\n        07: e;\n"));
+        let out = insert(SRC, date(2021, Jan, 1), "e;");
+        assert_eq!(out, format!("{}\n2021:\n    Jan:\n        01: e;\n", SRC));
+        let out = insert(SRC, date(2019, Jan, 1), "e;");
+        assert_eq!(out, format!("2019:\n    Jan:\n        01: e;\n\n{}", SRC));
+        let out = insert("", date(2019, Jan, 1), "e;");
+        assert_eq!(out, "2019:\n    Jan:\n        01: e;\n");
+    }
+}
@@ -8,7 +8,7 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::util::{
-    date::{self, Date, Interval},
+    date::{self, Convention, Date, Interval},
     entry::{
         fields::{self, Category, Span},
         Entry,
@@ -67,7 +67,7 @@ pub struct Template<'i> {
 }
 
 /// Describes a field that expands to a tag
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Tag<'i>(Vec<TagItem<'i>>);
 
 /// Possible contents of a tag field expansion
@@ -180,6 +180,7 @@ pub fn instanciate<'i>(
     errs: &mut error::Record,
     items: ast::Ast<'i>,
     mut templates: HashMap<String, crate::load::template::Template<'i>>,
+    conv: Convention,
 ) -> (Vec<Entry>, Interval<Date>) {
     let mut entries = Vec::new();
     let mut timeframe = date::Interval::Empty;
@@ -194,7 +195,7 @@ pub fn instanciate<'i>(
                 templates.insert(name.to_string(), body);
             }
             Item::Instance(date, instance) => {
-                match instanciate_item(errs, instance, date, &templates) {
+                match instanciate_item(errs, instance, date, &templates, conv) {
                     Some(inst) => {
                         timeframe = timeframe.unite(inst.period().into_interval());
                         entries.push(inst);
@@ -207,7 +208,7 @@ pub fn instanciate<'i>(
                 path.pop();
                 path.push(file);
                 let filename = path.to_str().unwrap();
-                let contents = match std::fs::read_to_string(&filename) {
+                let contents = match std::fs::read_to_string(filename) {
                     Ok(contents) => contents,
                     Err(_) => {
                         errs.make("File not found")
@@ -217,12 +218,12 @@ pub fn instanciate<'i>(
                         continue 'ast;
                     }
                 };
-                let data = crate::load::parse::extract(filename, errs, &contents);
+                let data = crate::load::parse::extract(filename, errs, &contents, conv);
                 if errs.is_fatal() {
                     return (Vec::new(), crate::util::date::Interval::Empty);
                 }
                 let (pairs, period) =
-                    crate::load::template::instanciate(filename, errs, data, templates.clone());
+                    crate::load::template::instanciate(filename, errs, data, templates.clone(), conv);
                 if errs.is_fatal() {
                     return (Vec::new(), period);
                 } else {
@@ -249,6 +250,7 @@ fn instanciate_item(
     instance: Instance<'_>,
     date: Date,
     templates: &HashMap<String, Template>,
+    conv: Convention,
 ) -> Option<Entry> {
     let templ = match templates.get(instance.label) {
         None => {
@@ -264,7 +266,7 @@ fn instanciate_item(
         Some(t) => t,
     };
     let args = build_arguments(errs, &instance, templ)?;
-    perform_replacements(errs, &instance, templ, args, date)
+    perform_replacements(errs, &instance, templ, args, date, conv)
 }
 
 /// Construct `HashMap` of arguments
@@ -325,6 +327,7 @@ fn perform_replacements(
     templ: &Template,
     args: HashMap<String, Arg>,
     date: Date,
+    conv: Convention,
 ) -> Option<Entry> {
     let (value, used_val) = instantiate_amount(errs, inst, templ, &args)?;
     let (tag, used_tag) = instanciate_tag(errs, inst, templ, &args, date)?;
@@ -355,7 +358,7 @@ fn perform_replacements(
             _ => (),
         }
     }
-    Some(Entry::from(value, templ.cat, templ.span.period(date), tag))
+    Some(Entry::from(value, templ.cat, templ.span.period(date, conv), tag))
 }
 
 /// Expand amount
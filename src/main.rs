@@ -1,116 +1,1343 @@
-mod cli;
-mod util;
-mod load;
-
-use cli::{plot::Plotter, table::Table};
-use util::{
-    date::{Date, Duration, Interval, Month},
-    summary::Calendar,
+use billig::{
+    cli::{
+        color::{Palette, When},
+        format::{Keywords, SourceFmt},
+        plot::{CategoryColors, PlotKind, Plotter},
+        report::HtmlReport,
+        table::{self, Layout, Table},
+    },
+    export::{self, beancount::Beancount, ledger::Journal, Amortization},
+    import, ledger,
+    load::{self, config::Config, error, rules::Rules, syntax},
+    util::{
+        currency::AmountFmt,
+        date::{Convention, Date, Duration, Interval, Month, Weekday},
+        entry::{self, Category},
+        summary::{Align, Calendar},
+    },
+    Ledger,
 };
-use std::collections::{BTreeSet, HashMap};
+use std::collections::BTreeSet;
+use std::io::Write;
 
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use num_traits::FromPrimitive;
 
 fn main() {
     let matches = App::new("Billig")
         .version("0.2")
         .author("Vanille N. <neven.villani@gmail.com>")
         .about("Command-line DSL-powered budget manager")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
         .arg(
-            Arg::with_name("source")
-                .default_value("expenses.bil")
+            Arg::with_name("color")
+                .long("color")
+                .value_name("WHEN")
+                .help("Use colors in the output, 'auto' disables them when it is not a terminal or NO_COLOR is set")
+                .possible_values(&["auto", "always", "never"])
+                .default_value("auto")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
                 .value_name("FILE")
-                .help("Source file"),
+                .help("Configuration file, by default billig.toml in the working directory or a parent")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("currency")
+                .long("currency")
+                .value_name("SYMBOL")
+                .help("Currency symbol of amounts")
+                .default_value("€")
+                .global(true)
+                .takes_value(true),
         )
         .arg(
-            Arg::with_name("table")
-                .short("t")
-                .long("table")
-                .value_name("TABLE,...")
-                .help("Choose tables to print (day, week, month, year)")
+            Arg::with_name("currency-position")
+                .long("currency-position")
+                .value_name("POS")
+                .help("Print the currency symbol before or after the number")
+                .possible_values(&["before", "after"])
+                .default_value("after")
+                .global(true)
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("plot")
-                .short("p")
-                .long("plot")
-                .value_name("PLOT,...")
-                .help("Choose plots to print (day, week, month, year)")
+            Arg::with_name("decimal-sep")
+                .long("decimal-sep")
+                .value_name("CHAR")
+                .help("Decimal separator of amounts")
+                .default_value(".")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("group-sep")
+                .long("group-sep")
+                .value_name("CHAR")
+                .help("Separator of thousands, none by default")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("no-cents")
+                .long("no-cents")
+                .help("Round amounts to whole units")
+                .overrides_with("cents")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("cents")
+                .long("cents")
+                .help("Print cents, even if the configuration rounds amounts")
+                .overrides_with("no-cents")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("week-start")
+                .long("week-start")
+                .value_name("DAY")
+                .help("First day of weeks")
+                .possible_values(&Weekday::NAMES)
+                .default_value("Mon")
+                .global(true)
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("period")
-                .short("D")
-                .long("period")
-                .value_name("YY-MM-DD..YY-MM-DD")
-                .help("Choose range of dates to analyze")
+            Arg::with_name("year-start")
+                .long("year-start")
+                .value_name("MONTH")
+                .help("First month of years, e.g. Sep for an academic year, quarters and semesters follow")
+                .possible_values(&Month::NAMES)
+                .default_value("Jan")
+                .global(true)
                 .takes_value(true),
         )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Print summary tables in the terminal")
+                .arg(source_arg())
+                .arg(period_arg())
+                .arg(align_arg())
+                .arg(
+                    Arg::with_name("table")
+                        .short("t")
+                        .long("table")
+                        .value_name("TABLE,...")
+                        .help("Choose tables to print (day, week, month, quarter, semester, year), optionally several units each (2w)")
+                        .default_value("month")
+                        .takes_value(true),
+                )
+                .arg(splits_arg())
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("FORMAT")
+                        .help("Print a grid for the terminal or raw data for other tools")
+                        .possible_values(&["table", "csv", "tsv", "json", "markdown", "html"])
+                        .default_value("table")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("html")
+                        .long("html")
+                        .value_name("FILE")
+                        .help("Write a standalone page with the tables, plots and largest entries")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("plot")
+                .about("Generate plots as .svg or .png files")
+                .arg(source_arg())
+                .arg(period_arg())
+                .arg(align_arg())
+                .arg(
+                    Arg::with_name("plot")
+                        .short("p")
+                        .long("plot")
+                        .value_name("PLOT,...")
+                        .help("Choose plots to print (day, week, month, quarter, semester, year), optionally several units each (3m)")
+                        .default_value("month")
+                        .takes_value(true),
+                )
+                .arg(splits_arg())
+                .arg(
+                    Arg::with_name("plot-dir")
+                        .short("d")
+                        .long("plot-dir")
+                        .value_name("DIR")
+                        .help("Directory of the plots, created if needed")
+                        .default_value(".")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("plot-name")
+                        .short("n")
+                        .long("plot-name")
+                        .value_name("PATTERN")
+                        .help("Name of the files, {source}, {kind}, {duration}, {start}, {end} and {ext} are replaced")
+                        .default_value("{source}-{kind}-{duration}-{start}-{end}.{ext}")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("plot-kind")
+                        .short("k")
+                        .long("plot-kind")
+                        .value_name("KIND")
+                        .help("Draw cumulative steps, stacked areas, bars, the balance, a pie of expenses or a heatmap of days")
+                        .possible_values(&PlotKind::NAMES)
+                        .default_value("cumulative")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("palette")
+                        .long("palette")
+                        .value_name("PALETTE")
+                        .help("Colors of the categories, [colors] in billig.toml overrides single ones")
+                        .possible_values(&["colorblind", "classic"])
+                        .default_value("colorblind")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("stdout")
+                        .long("stdout")
+                        .help("Print the plot instead of writing a file, only one plot can be chosen"),
+                )
+                .arg(
+                    Arg::with_name("plot-target")
+                        .long("plot-target")
+                        .value_name("TARGET")
+                        .help("Write .svg or .png files, or draw cumulative plots in the terminal")
+                        .possible_values(&["svg", "png", "term"])
+                        .default_value("svg")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("dpi")
+                        .long("dpi")
+                        .value_name("DPI")
+                        .help("Resolution of .png files, 96 gives one pixel per unit of the .svg")
+                        .default_value("96")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .long("width")
+                        .value_name("COLUMNS")
+                        .help("Width of plots in the terminal, defaults to $COLUMNS or 80")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Validate a file, exit with a nonzero status on errors")
+                .arg(source_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("List entries")
+                .arg(source_arg())
+                .arg(period_arg())
+                .arg(
+                    Arg::with_name("category")
+                        .short("c")
+                        .long("category")
+                        .value_name("CAT")
+                        .help("Only show entries of this category (Food, Mov, Home, ...)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Rewrite a file in canonical form")
+                .arg(source_arg())
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .help("Do not write anything, exit with a nonzero status if the file is not formatted"),
+                )
+                .arg(
+                    Arg::with_name("keywords")
+                        .short("k")
+                        .long("keywords")
+                        .value_name("STYLE")
+                        .help("Print keywords (val, type, span, tag) in full or omit them")
+                        .possible_values(&["full", "short"])
+                        .default_value("full")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("add")
+                .about("Insert an entry in a file at its chronological position")
+                .setting(AppSettings::AllowNegativeNumbers)
+                .arg(
+                    Arg::with_name("file")
+                        .short("f")
+                        .long("file")
+                        .value_name("FILE")
+                        .help("File to modify")
+                        .default_value("expenses.bil")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("date")
+                        .short("d")
+                        .long("date")
+                        .value_name("YYYY-Mmm-DD")
+                        .help("Date of the entry, defaults to today")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("entry")
+                        .value_name("FIELDS")
+                        .help("Contents of the entry, e.g. -12.5 Food Day \"Lunch\" or !restaurant 30 place=Foo")
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Convert a bank statement to entries")
+                .arg(
+                    Arg::with_name("statement")
+                        .value_name("STATEMENT")
+                        .help("Export of a bank account (.csv, .ofx, .qfx or .qif) or beancount file (.beancount or .bean)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("rules")
+                        .short("r")
+                        .long("rules")
+                        .value_name("FILE")
+                        .help("Profile of the statement and categorisation rules (not needed for beancount)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("map")
+                        .short("m")
+                        .long("map")
+                        .value_name("ACCOUNT=TYPE")
+                        .help("Category of the postings to a beancount account and its subaccounts")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("ledger")
+                        .short("l")
+                        .long("ledger")
+                        .value_name("FILE")
+                        .help("Skip transactions already in this file")
+                        .default_value("expenses.bil")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write entries to a file instead of the standard output")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Convert entries to the format of another accounting tool")
+                .arg(source_arg())
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format")
+                        .possible_values(&["ledger", "beancount"])
+                        .default_value("ledger")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("amortize")
+                        .short("a")
+                        .long("amortize")
+                        .value_name("STEP")
+                        .help("Spread entries over their period with one posting per day or month (ledger only)")
+                        .possible_values(&["none", "day", "month"])
+                        .default_value("none")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("account")
+                        .long("account")
+                        .value_name("ACCOUNT")
+                        .help("Account that pays for expenses and receives incomes")
+                        .default_value("Assets:Checking")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("commodity")
+                        .long("commodity")
+                        .value_name("CURRENCY")
+                        .help("Currency of the amounts")
+                        .default_value("EUR")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write to a file instead of the standard output")
+                        .takes_value(true),
+                ),
+        )
         .get_matches();
-    let mut errs = load::error::Record::new();
+    let (name, sub) = match matches.subcommand() {
+        (name, Some(sub)) => (name, sub),
+        _ => unreachable!(),
+    };
+    // errors in the configuration file are colored according to the command line only
+    let defaults = Args { matches: sub, config: &Config::default(), amount_fmt: AmountFmt::default() };
+    let config = match load_config(&defaults) {
+        Some(config) => config,
+        None => std::process::exit(1),
+    };
+    let args = Args { matches: sub, config: &config, amount_fmt: AmountFmt::default() };
+    let args = match amount_fmt(&args) {
+        Some(amount_fmt) => Args { amount_fmt, ..args },
+        None => std::process::exit(1),
+    };
+    let status = match name {
+        "report" => cmd_report(&args),
+        "plot" => cmd_plot(&args),
+        "check" => cmd_check(&args),
+        "show" => cmd_show(&args),
+        "fmt" => cmd_fmt(&args),
+        "add" => cmd_add(&args),
+        "import" => cmd_import(&args),
+        "export" => cmd_export(&args),
+        _ => unreachable!(),
+    };
+    std::process::exit(status);
+}
+
+/// Command-line arguments of a subcommand, with defaults from `billig.toml`
+struct Args<'a> {
+    matches: &'a ArgMatches<'a>,
+    config: &'a Config,
+    /// how tables, plots and entries print amounts
+    amount_fmt: AmountFmt,
+}
+
+impl<'a> Args<'a> {
+    /// Setting of the configuration file that provides the default of an argument
+    fn setting(name: &str) -> Option<&'static str> {
+        Some(match name {
+            "source" | "file" | "ledger" => "source",
+            "color" => "color",
+            "align" => "align",
+            "week-start" => "week-start",
+            "year-start" => "year-start",
+            "table" => "report.durations",
+            "plot" => "plot.durations",
+            "plot-dir" => "plot.dir",
+            "plot-name" => "plot.name",
+            "plot-kind" => "plot.kind",
+            "palette" => "plot.palette",
+            "plot-target" => "plot.target",
+            "dpi" => "plot.dpi",
+            "currency" => "currency.symbol",
+            "currency-position" => "currency.position",
+            "decimal-sep" => "currency.decimal-sep",
+            "group-sep" => "currency.group-sep",
+            _ => return None,
+        })
+    }
+
+    /// Value on the command line, or in the configuration, or the default value
+    fn value_of(&self, name: &str) -> Option<&'a str> {
+        if self.matches.occurrences_of(name) == 0 {
+            if let Some(value) = Self::setting(name).and_then(|s| self.config.get(s)) {
+                return Some(value);
+            }
+        }
+        self.matches.value_of(name)
+    }
+
+    fn values_of(&self, name: &str) -> Option<clap::Values<'a>> {
+        self.matches.values_of(name)
+    }
+
+    fn is_present(&self, name: &str) -> bool {
+        match name {
+            "no-cents" if !self.matches.is_present(name) => {
+                !self.matches.is_present("cents") && self.config.get("currency.cents") == Some("false")
+            }
+            _ => self.matches.is_present(name),
+        }
+    }
+
+    fn occurrences_of(&self, name: &str) -> u64 {
+        self.matches.occurrences_of(name)
+    }
+
+    /// Empty record of diagnostics, with the levels of warnings of the configuration
+    fn record(&self) -> error::Record {
+        error::Record::new().with_levels(self.config.lints())
+    }
+
+    /// Print diagnostics on stderr, in color unless `--color` or stderr rule it out
+    fn report(&self, errs: &error::Record) {
+        if Palette::detect_stderr(when(self)) == Palette::Plain {
+            eprint!("{}", errs);
+        } else {
+            eprint!("{}", errs.colored());
+        }
+    }
+}
+
+/// Read `--config` or the `billig.toml` closest to the working directory
+fn load_config(matches: &Args) -> Option<Config> {
+    let path = match matches.value_of("config") {
+        Some(path) => Some(std::path::PathBuf::from(path)),
+        None => Config::find(),
+    };
+    let path = match path {
+        Some(path) => path,
+        None => return Some(Config::default()),
+    };
+    let mut errs = error::Record::new();
+    let config = Config::load(&path, &mut errs);
+    matches.report(&errs);
+    config
+}
+
+fn when(matches: &Args) -> When {
+    match matches.value_of("color") {
+        Some("always") => When::Always,
+        Some("never") => When::Never,
+        _ => When::Auto,
+    }
+}
+
+fn palette(matches: &Args) -> Palette {
+    Palette::detect(when(matches))
+}
+
+/// Read the options that control how amounts are printed
+fn amount_fmt(matches: &Args) -> Option<AmountFmt> {
+    let single = |name: &str| {
+        let value = matches.value_of(name)?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => {
+                eprintln!("'{}' is not a valid separator for --{}, expected a single character", value, name);
+                None
+            }
+        }
+    };
+    let decimal = single("decimal-sep")?;
+    let grouping = match matches.value_of("group-sep") {
+        Some(_) => Some(single("group-sep")?),
+        None => None,
+    };
+    Some(
+        AmountFmt::default()
+            .with_symbol(matches.value_of("currency").unwrap())
+            .with_prefix(matches.value_of("currency-position") == Some("before"))
+            .with_decimal(decimal)
+            .with_grouping(grouping)
+            .with_cents(!matches.is_present("no-cents")),
+    )
+}
+
+fn convention(matches: &Args) -> Convention {
+    // both are restricted to valid names by clap and by the configuration
+    Convention::default()
+        .with_week_start(matches.value_of("week-start").unwrap().parse().unwrap())
+        .with_year_start(matches.value_of("year-start").unwrap().parse().unwrap())
+}
+
+fn source_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("source")
+        .default_value("expenses.bil")
+        .value_name("FILE")
+        .help("Source file")
+}
+
+fn period_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("period")
+        .short("D")
+        .long("period")
+        .value_name("YY-MM-DD..YY-MM-DD")
+        .help("Choose range of dates to analyze")
+        .takes_value(true)
+}
+
+fn align_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("align")
+        .long("align")
+        .value_name("ALIGN")
+        .help("Start periods on the first day of weeks, months and years, or on the first day of data")
+        .possible_values(&["natural", "none"])
+        .default_value("natural")
+        .takes_value(true)
+}
+
+fn splits_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("splits")
+        .long("splits")
+        .value_name("DATE,...")
+        .help("Add periods between consecutive dates, the last one excluded")
+        .takes_value(true)
+}
+
+/// Where the periods of the calendars start
+fn align(matches: &Args) -> Align {
+    match matches.value_of("align") {
+        Some("none") => Align::Start,
+        _ => Align::Natural,
+    }
+}
+
+/// Load the source file restricted to the requested period
+///
+/// Errors are printed, and `None` is returned if any of them is fatal
+fn load_ledger(matches: &Args) -> Option<Ledger> {
     // Get the period right now: we want these errors before we start parsing the file
-    let arg_timeframe = match parse_arg_timeframe(&matches, &mut errs) {
-        Some(timeframe) => timeframe,
-        None => {
-            println!("{}", errs);
-            return
+    let arg_timeframe = match parse_arg_timeframe(matches) {
+        Ok(timeframe) => timeframe,
+        Err(errs) => {
+            matches.report(&errs);
+            return None;
         }
     };
     let filename = matches.value_of("source").unwrap();
-    let (entries, mut timeframe) = load::read_entries(filename, &mut errs);
-    println!("{}", errs);
-    if let Some(lst) = entries {
-        timeframe = timeframe.intersect(arg_timeframe);
-        let tables = durations(&matches, "table");
-        let plots = durations(&matches, "plot");
-        let mut calendars: HashMap<Duration, Calendar> = tables
-            .union(&plots)
-            .map(|&k| (k, Calendar::from_spacing(timeframe.into_between(), k, 1)))
-            .collect();
-        for (_, cal) in calendars.iter_mut() {
-            cal.register(&lst);
-        }
-        for t in tables {
-            let tbl = Table::from(calendars[&t].contents()).with_title(t.text_frequency());
-            println!("{}", tbl);
-        }
-        for p in plots {
-            Plotter::from(calendars[&p].contents()).print_cumulative_plot(p.text_frequency());
-        }
-    }
-}
-
-fn durations(matches: &clap::ArgMatches, label: &str) -> BTreeSet<Duration> {
-    if let Some(s) = matches.value_of(label) {
-        s.split(',')
-            .filter_map(|v| {
-                Some(match v {
-                    "day" | "d" => Duration::Day,
-                    "week" | "w" => Duration::Week,
-                    "month" | "m" => Duration::Month,
-                    "year" | "y" => Duration::Year,
-                    other => {
-                        eprintln!("'{}' is not a valid duration", other);
-                        eprintln!("Expected one of 'day','week','month','year' or 'd','w','m','y'");
-                        return None;
-                    }
-                })
-            })
-            .collect()
+    match Ledger::load_with(filename, convention(matches), matches.record()) {
+        Ok(ledger) => {
+            matches.report(ledger.warnings());
+            Some(ledger.restrict(arg_timeframe))
+        }
+        Err(errs) => {
+            matches.report(&errs);
+            None
+        }
+    }
+}
+
+fn cmd_report(matches: &Args) -> i32 {
+    let ledger = match load_ledger(matches) {
+        Some(ledger) => ledger,
+        None => return 1,
+    };
+    if let Some(file) = matches.value_of("html") {
+        return write_html_report(matches, &ledger, file);
+    }
+    let layout = match matches.value_of("output") {
+        Some("csv") => Layout::Csv,
+        Some("tsv") => Layout::Tsv,
+        Some("json") => Layout::Json,
+        Some("markdown") => Layout::Markdown,
+        Some("html") => Layout::Html,
+        _ => Layout::Grid,
+    };
+    let periods = match periods(matches, "table") {
+        Some(periods) => periods,
+        None => return 1,
+    };
+    let tables = periods
+        .into_iter()
+        .map(|t| {
+            let cal = t.calendar(&ledger, align(matches));
+            Table::from(cal.contents())
+                .with_title(t.name(align(matches), convention(matches)))
+                .with_layout(layout)
+                .with_palette(palette(matches))
+                .with_amount_fmt(matches.amount_fmt.clone())
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    match layout {
+        // a single document with one object per table
+        Layout::Json => println!("[{}]", tables.join(",\n")),
+        Layout::Html => print!("{}", table::html_document("Billig report", &tables.join("\n"))),
+        Layout::Grid => tables.iter().for_each(|t| println!("{}", t)),
+        // separated by a blank line
+        Layout::Csv | Layout::Tsv | Layout::Markdown => print!("{}", tables.join("\n")),
+    }
+    0
+}
+
+/// Palette of the plots, with the colors of single categories from the configuration
+fn category_colors(matches: &Args) -> CategoryColors {
+    let mut colors = match matches.value_of("palette") {
+        Some("classic") => CategoryColors::classic(),
+        _ => CategoryColors::colorblind(),
+    };
+    // colors of the configuration were checked when it was loaded
+    for i in 0..Category::COUNT {
+        let cat = Category::from_usize(i).unwrap();
+        if let Some(color) = matches.config.get(&format!("colors.{}", cat.keyword())) {
+            colors = colors.with(cat, color);
+        }
+    }
+    colors
+}
+
+/// Number of entries listed by the HTML report
+const REPORT_ENTRIES: usize = 50;
+
+fn write_html_report(matches: &Args, ledger: &Ledger, file: &str) -> i32 {
+    let colors = category_colors(matches);
+    let periods = match periods(matches, "table") {
+        Some(periods) => periods,
+        None => return 1,
+    };
+    let mut report = HtmlReport::new("Billig report").with_amount_fmt(matches.amount_fmt.clone());
+    for t in periods {
+        let cal = t.calendar(ledger, align(matches));
+        let data = cal.contents();
+        let table = Table::from(data)
+            .with_title(t.name(align(matches), convention(matches)))
+            .with_layout(Layout::Html)
+            .with_amount_fmt(matches.amount_fmt.clone());
+        let plot = Plotter::from(data)
+            .with_colors(colors.clone())
+            .with_amount_fmt(matches.amount_fmt.clone())
+            .with_tooltips(true)
+            .cumulative_svg();
+        report = report.with_section(t.name(align(matches), convention(matches)), format!("{}{}", table, plot.unwrap_or_default()));
+    }
+    let entries = ledger.during(ledger.timeframe().into_between()).collect::<Vec<_>>();
+    let report = report.with_entries(&entries, REPORT_ENTRIES);
+    if let Err(e) = std::fs::write(file, report.to_string()) {
+        eprintln!("Could not write '{}': {}", file, e);
+        return 1;
+    }
+    0
+}
+
+/// Highest resolution of `--plot-target png`, a plot is then about 8000 pixels wide
+const MAX_DPI: u32 = 600;
+
+fn cmd_plot(matches: &Args) -> i32 {
+    let ledger = match load_ledger(matches) {
+        Some(ledger) => ledger,
+        None => return 1,
+    };
+    let kind = matches.value_of("plot-kind").unwrap().parse::<PlotKind>().unwrap();
+    // the heatmap has one square per day whatever the durations
+    let plots = match kind {
+        PlotKind::Heatmap => Some(vec![Periods::Every(Duration::Day, 1)]),
+        _ => periods(matches, "plot"),
+    };
+    let plots = match plots {
+        Some(plots) => plots,
+        None => return 1,
+    };
+    let colors = category_colors(matches);
+    let target = matches.value_of("plot-target").unwrap();
+    let term = target == "term";
+    if term && kind != PlotKind::Cumulative {
+        eprintln!("Only cumulative plots can be drawn in the terminal, not {}", kind.keyword());
+        return 1;
+    }
+    let width = match matches
+        .value_of("width")
+        .map(String::from)
+        .or_else(|| std::env::var("COLUMNS").ok())
+        .map(|w| w.parse::<usize>())
+    {
+        None => 80,
+        Some(Ok(width)) => width,
+        Some(Err(_)) => {
+            eprintln!("Width should be a number of columns");
+            return 1;
+        }
+    };
+    let dpi = match matches.value_of("dpi").unwrap().parse::<u32>() {
+        Ok(dpi) if (1..=MAX_DPI).contains(&dpi) => dpi,
+        _ => {
+            eprintln!("Resolution should be a number of dots per inch between 1 and {}", MAX_DPI);
+            return 1;
+        }
+    };
+    let stdout = matches.is_present("stdout");
+    if stdout && plots.len() > 1 {
+        eprintln!("--stdout can only print one plot, {} were chosen", plots.len());
+        return 1;
+    }
+    let dir = std::path::Path::new(matches.value_of("plot-dir").unwrap());
+    if !stdout && !term {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("Could not create '{}': {}", dir.display(), e);
+            return 1;
+        }
+    }
+    let source = std::path::Path::new(matches.value_of("source").unwrap())
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut status = 0;
+    for p in plots {
+        let cal = p.calendar(&ledger, align(matches));
+        let data = cal.contents();
+        let no_data = || eprintln!("No data for the {} {} plot", p.name(align(matches), convention(matches)).to_lowercase(), kind.keyword());
+        let (start, end) = match (data.first(), data.last()) {
+            (Some(first), Some(last)) => (export::iso_date(first.period().0), export::iso_date(last.period().1)),
+            _ => {
+                no_data();
+                continue;
+            }
+        };
+        let title = format!("{} {} plot of {}, {} to {}", p.name(align(matches), convention(matches)), kind.keyword(), source, start, end);
+        if term {
+            let plotter = Plotter::from(data)
+                .with_colors(colors.clone())
+                .with_amount_fmt(matches.amount_fmt.clone())
+                .with_title(title);
+            print!("{}", plotter.term(width, palette(matches)).unwrap());
+            continue;
+        }
+        let plotter = Plotter::from(data)
+            .with_colors(colors.clone())
+            .with_amount_fmt(matches.amount_fmt.clone())
+            .with_convention(ledger.convention())
+            .with_title(title);
+        let contents = match target {
+            "png" => match plotter.png(kind, dpi as f64).transpose() {
+                Ok(contents) => contents,
+                Err(e) => {
+                    eprintln!("Could not draw the {} {} plot: {}", p.name(align(matches), convention(matches)).to_lowercase(), kind.keyword(), e);
+                    eprintln!("A lower --dpi gives a smaller image");
+                    status = 1;
+                    continue;
+                }
+            },
+            _ => plotter.svg(kind).map(String::into_bytes),
+        };
+        let contents = match contents {
+            Some(contents) => contents,
+            None => {
+                no_data();
+                continue;
+            }
+        };
+        if stdout {
+            if let Err(e) = std::io::stdout().write_all(&contents) {
+                eprintln!("Could not print the plot: {}", e);
+                status = 1;
+            }
+            continue;
+        }
+        let name = matches
+            .value_of("plot-name")
+            .unwrap()
+            .replace("{source}", &source)
+            .replace("{kind}", kind.keyword())
+            .replace("{duration}", &p.name(align(matches), convention(matches)).replace(' ', "-"))
+            .replace("{start}", &start)
+            .replace("{end}", &end)
+            .replace("{ext}", target);
+        let path = dir.join(name);
+        if let Err(e) = std::fs::write(&path, contents) {
+            eprintln!("Could not write '{}': {}", path.display(), e);
+            status = 1;
+        }
+    }
+    status
+}
+
+fn cmd_check(matches: &Args) -> i32 {
+    let filename = matches.value_of("source").unwrap();
+    match Ledger::load_with(filename, convention(matches), matches.record()) {
+        Ok(ledger) => {
+            matches.report(ledger.warnings());
+            println!("{}: {} entries", filename, ledger.entries().len());
+            0
+        }
+        Err(errs) => {
+            matches.report(&errs);
+            1
+        }
+    }
+}
+
+fn cmd_show(matches: &Args) -> i32 {
+    let category = match matches.value_of("category") {
+        None => None,
+        Some(s) => match s.parse::<Category>() {
+            Ok(cat) => Some(cat),
+            Err(()) => {
+                eprintln!("'{}' is not a valid category", s);
+                eprintln!("Expected one of Pay, Home, Pro, Clean, Mov, Tech, Food, Fun");
+                return 1;
+            }
+        },
+    };
+    let ledger = match load_ledger(matches) {
+        Some(ledger) => ledger,
+        None => return 1,
+    };
+    let timeframe = ledger.timeframe().into_between();
+    let mut entries = ledger
+        .filter(|e| category.map(|c| e.category() == c).unwrap_or(true))
+        .filter(|e| e.period().0 <= timeframe.1 && timeframe.0 <= e.period().1)
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.period().0);
+    for entry in entries {
+        println!("{}", entry.text(&matches.amount_fmt));
+    }
+    0
+}
+
+fn cmd_fmt(matches: &Args) -> i32 {
+    let filename = matches.value_of("source").unwrap();
+    let contents = match std::fs::read_to_string(filename) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("Could not read '{}': {}", filename, e);
+            return 1;
+        }
+    };
+    let mut errs = matches.record();
+    let mut doc = match syntax::extract(filename, &mut errs, &contents) {
+        Some(doc) => doc,
+        None => {
+            matches.report(&errs);
+            return 1;
+        }
+    };
+    doc.sort();
+    let keywords = match matches.value_of("keywords") {
+        Some("short") => Keywords::Short,
+        _ => Keywords::Full,
+    };
+    let formatted = SourceFmt::from(&doc).with_keywords(keywords).to_string();
+    if matches.is_present("check") {
+        if formatted == contents {
+            0
+        } else {
+            println!("{}: not formatted", filename);
+            1
+        }
+    } else if formatted != contents {
+        match std::fs::write(filename, formatted) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Could not write '{}': {}", filename, e);
+                1
+            }
+        }
     } else {
-        BTreeSet::new()
+        0
     }
 }
 
-fn parse_arg_timeframe(args: &clap::ArgMatches, errs: &mut load::error::Record) -> Option<Interval<Date>> {
-    let value = match args.value_of("period") {
-        Some(arg) => arg,
-        None => return Some(Interval::Unbounded),
+fn cmd_add(matches: &Args) -> i32 {
+    let filename = matches.value_of("file").unwrap();
+    let date = match matches.value_of("date") {
+        None => Date::today(),
+        Some(s) => match s.parse::<Date>() {
+            Ok(date) => date,
+            Err(()) => {
+                eprintln!("'{}' is not a valid date", s);
+                eprintln!("Expected YYYY-Mmm-DD, e.g. 2021-Mar-15");
+                return 1;
+            }
+        },
+    };
+    let entry = entry_from_args(&matches.values_of("entry").unwrap().collect::<Vec<_>>());
+    // a missing file is created, any other failure leaves the file untouched
+    let contents = match std::fs::read_to_string(filename) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            eprintln!("Could not read '{}': {}", filename, e);
+            return 1;
+        }
+    };
+    let mut errs = matches.record();
+    let doc = match syntax::extract(filename, &mut errs, &contents) {
+        Some(doc) => doc,
+        None => {
+            matches.report(&errs);
+            return 1;
+        }
     };
-    let pseudo_span = pest::Span::new(value, 0, value.len()).unwrap();
-    let pseudo_path = "cmdline";
-    let pseudo_loc = &(pseudo_path, pseudo_span);
-    let partial_interval = Interval::parse(pseudo_path, errs, value)?;
-    let interval = partial_interval.make(errs, pseudo_loc, Date::today())?;
-    Some(interval)
+    let updated = doc.insert(date, &entry);
+    // the whole file is checked again so that the entry is validated
+    // in the context of the templates it can use
+    let mut errs = matches.record();
+    if let (None, _) = load::read_source(filename, &mut errs, &updated, convention(matches)) {
+        matches.report(&errs);
+        eprintln!("Entry was not added to '{}'", filename);
+        return 1;
+    }
+    matches.report(&errs);
+    match std::fs::write(filename, updated) {
+        Ok(()) => {
+            println!("{}: {} {}", filename, date, entry);
+            0
+        }
+        Err(e) => {
+            eprintln!("Could not write '{}': {}", filename, e);
+            1
+        }
+    }
+}
+
+fn cmd_import(matches: &Args) -> i32 {
+    let mut errs = matches.record();
+    let statement = matches.value_of("statement").unwrap();
+    let extension = std::path::Path::new(statement)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    let candidates = match extension.as_deref() {
+        Some("beancount") | Some("bean") => match account_map(matches) {
+            Some(map) => import::beancount::read(statement, &mut errs, &map),
+            None => return 1,
+        },
+        _ => read_statement(matches, &mut errs, statement, extension.as_deref()),
+    };
+    let mut candidates = match candidates {
+        Some(candidates) => candidates,
+        None => {
+            matches.report(&errs);
+            return 1;
+        }
+    };
+    // the default ledger is allowed not to exist
+    let ledger = matches.value_of("ledger").unwrap();
+    if matches.occurrences_of("ledger") > 0 || std::path::Path::new(ledger).exists() {
+        match Ledger::load_with(ledger, convention(matches), matches.record()) {
+            Ok(ledger) => {
+                let skipped = import::remove_known(&mut candidates, ledger.entries(), ledger.convention());
+                if skipped > 0 {
+                    eprintln!("Skipped {} transactions already in the ledger", skipped);
+                }
+            }
+            Err(e) => {
+                matches.report(&errs);
+                matches.report(&e);
+                return 1;
+            }
+        }
+    }
+    matches.report(&errs);
+    write_output(matches, &import::to_source(&candidates))
+}
+
+/// Read and categorise the transactions of a bank statement
+fn read_statement(
+    matches: &Args,
+    errs: &mut error::Record,
+    statement: &str,
+    extension: Option<&str>,
+) -> Option<Vec<import::Candidate>> {
+    let rules = match matches.value_of("rules") {
+        Some(rules) => Rules::load(rules, errs)?,
+        None => {
+            errs.make("Missing rules")
+                .text(format!("'{}' is a bank statement", statement))
+                .hint("give a rules file with --rules");
+            return None;
+        }
+    };
+    let transactions = match extension {
+        Some("ofx") | Some("qfx") => import::ofx::read(statement, errs),
+        Some("qif") => import::qif::read(statement, errs, &rules.profile),
+        _ => import::csv::read(statement, errs, &rules.profile),
+    }?;
+    Some(import::categorize(errs, &rules, transactions))
+}
+
+/// Parse the `--map ACCOUNT=TYPE` arguments
+fn account_map(matches: &Args) -> Option<Vec<(String, Category)>> {
+    let mut map = Vec::new();
+    for item in matches.values_of("map").into_iter().flatten() {
+        let parsed = item
+            .split_once('=')
+            .and_then(|(account, cat)| cat.trim().parse::<Category>().ok().map(|cat| (account.trim().to_string(), cat)));
+        match parsed {
+            Some(mapping) => map.push(mapping),
+            None => {
+                eprintln!("Invalid mapping '{}', expected ACCOUNT=TYPE with TYPE one of Pay, Food, Tech, Mov, Pro, Clean, Home, Fun", item);
+                return None;
+            }
+        }
+    }
+    Some(map)
+}
+
+fn cmd_export(matches: &Args) -> i32 {
+    let ledger = match load_ledger(matches) {
+        Some(ledger) => ledger,
+        None => return 1,
+    };
+    let amortization = match matches.value_of("amortize") {
+        Some("day") => Amortization::Daily,
+        Some("month") => Amortization::Monthly,
+        _ => Amortization::None,
+    };
+    let beancount = matches.value_of("format") == Some("beancount");
+    if beancount && amortization != Amortization::None {
+        // the period is kept as metadata instead, so that the file can be imported back
+        eprintln!("--amortize is only supported by the ledger format");
+        eprintln!("Beancount transactions keep the period of the entry in '{}' metadata", export::beancount::PERIOD_KEY);
+        return 1;
+    }
+    let account = matches.value_of("account").unwrap();
+    let commodity = matches.value_of("commodity").unwrap();
+    let text = match matches.value_of("format") {
+        Some("beancount") => Beancount::from(ledger.entries())
+            .with_account(account)
+            .with_commodity(commodity)
+            .to_string(),
+        _ => Journal::from(ledger.entries())
+            .with_amortization(amortization)
+            .with_account(account)
+            .with_commodity(commodity)
+            .to_string(),
+    };
+    write_output(matches, &text)
+}
+
+/// Print `text` or write it to the file given by `--output`
+fn write_output(matches: &Args, text: &str) -> i32 {
+    match matches.value_of("output") {
+        None => {
+            print!("{}", text);
+            0
+        }
+        Some(output) => match std::fs::write(output, text) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Could not write '{}': {}", output, e);
+                1
+            }
+        },
+    }
+}
+
+/// Build the text of an entry from command line arguments
+///
+/// Quotes are added to arguments that are not amounts or builtins,
+/// since the shell will have removed them. Consecutive words form a single
+/// tag, which also takes the words after it that are not missing fields.
+fn entry_from_args(args: &[&str]) -> String {
+    let quote = |s: &str| {
+        if is_amount(s) || s.starts_with('"') {
+            s.to_string()
+        } else {
+            format!("\"{}\"", s)
+        }
+    };
+    let mut fields = Vec::new();
+    match args {
+        // already written in the syntax of the file
+        [arg] if arg.contains(',') || arg.contains('"') || arg.ends_with(';') => {
+            fields.push(arg.trim_end_matches(';').to_string());
+        }
+        [name, args @ ..] if name.starts_with('!') => {
+            let mut expand = name.to_string();
+            for arg in args {
+                expand.push(' ');
+                match arg.split_once('=') {
+                    Some((key, value)) => expand.push_str(&format!("{}={}", key, quote(value))),
+                    None => expand.push_str(&quote(arg)),
+                }
+            }
+            fields.push(expand);
+        }
+        _ => {
+            let mut args = args.iter();
+            let (mut has_val, mut has_type, mut has_span) = (false, false, false);
+            // position of the unquoted tag and its words
+            let mut tag: Option<(usize, Vec<&str>)> = None;
+            while let Some(arg) = args.next() {
+                // a field that is already defined is read as a word of the tag
+                let defined = (has_val && is_amount(arg))
+                    || (has_span && is_span(arg))
+                    || (has_type && arg.parse::<Category>().is_ok());
+                match *arg {
+                    "val" | "type" | "span" | "period" | "tag" => {
+                        let value = args.next().copied().unwrap_or_default();
+                        has_val |= *arg == "val";
+                        has_type |= *arg == "type";
+                        has_span |= *arg == "span";
+                        let value = if *arg == "tag" { quote(value) } else { value.to_string() };
+                        fields.push(format!("{} {}", arg, value));
+                    }
+                    _ if defined && tag.is_some() => tag.as_mut().unwrap().1.push(arg),
+                    _ if is_amount(arg) => {
+                        // a count after a duration, as in `Day 2`
+                        let after_span = fields.last().map(|f: &String| is_span(f));
+                        if has_val && after_span.unwrap_or(false) && !arg.starts_with('-') {
+                            let span = fields.pop().unwrap();
+                            fields.push(format!("{} {}", span, arg));
+                        } else {
+                            has_val = true;
+                            fields.push(arg.to_string());
+                        }
+                    }
+                    _ if is_span(arg) => {
+                        has_span = true;
+                        fields.push(arg.to_string());
+                    }
+                    _ if arg.parse::<Category>().is_ok() => {
+                        has_type = true;
+                        fields.push(arg.to_string());
+                    }
+                    _ if arg.starts_with('"') => fields.push(arg.to_string()),
+                    _ => match &mut tag {
+                        Some((_, words)) => words.push(arg),
+                        None => {
+                            tag = Some((fields.len(), vec![arg]));
+                            fields.push(String::new());
+                        }
+                    },
+                }
+            }
+            if let Some((i, words)) = tag {
+                fields[i] = quote(&words.join(" "));
+            }
+        }
+    }
+    format!("{};", fields.join(", "))
+}
+
+fn is_amount(s: &str) -> bool {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let (int, dec) = s.split_once('.').unwrap_or((s, "0"));
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    !int.is_empty() && digits(int) && (1..=2).contains(&dec.len()) && digits(dec)
+}
+
+/// `Duration` or `Duration<Window>`
+fn is_span(s: &str) -> bool {
+    let (duration, window) = match s.strip_suffix('>').and_then(|s| s.split_once('<')) {
+        Some((duration, window)) => (duration, Some(window)),
+        None => (s, None),
+    };
+    duration.parse::<entry::Duration>().is_ok()
+        && window.map(|w| w.parse::<entry::Window>().is_ok()).unwrap_or(true)
+}
+
+/// Periods of a table or a plot
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Periods {
+    /// steps of a number of days, weeks, months, quarters, semesters or years
+    Every(Duration, usize),
+    /// between consecutive dates, the last one excluded
+    Splits(Vec<Date>),
+}
+
+impl Periods {
+    /// Title of the table or plot, with the first day of weeks or years when it is not the usual one
+    fn name(&self, align: Align, conv: Convention) -> String {
+        let name = match self {
+            Periods::Every(duration, 1) => duration.text_frequency().to_string(),
+            Periods::Every(duration, count) => format!("Every {}", duration.text_count(*count)),
+            Periods::Splits(_) => return String::from("Custom"),
+        };
+        let start = match self {
+            Periods::Every(Duration::Week, _) if conv.week_start() != Weekday::Mon => conv.week_start().to_string(),
+            Periods::Every(Duration::Quarter | Duration::Semester | Duration::Year, _) if conv.year_start() != Month::Jan => {
+                conv.year_start().to_string()
+            }
+            _ => return name,
+        };
+        match align {
+            Align::Natural => format!("{} from {}", name, start),
+            Align::Start => name,
+        }
+    }
+
+    fn calendar(&self, ledger: &Ledger, align: Align) -> Calendar {
+        match self {
+            Periods::Every(duration, count) => ledger.calendar(*duration, *count, align),
+            Periods::Splits(splits) => ledger.calendar_from_splits(splits),
+        }
+    }
+}
+
+/// Durations of `label` such as `2w,month`, then the periods of `--splits`
+///
+/// Unless `label` is on the command line, `--splits` replaces the default durations.
+/// Invalid durations are skipped, invalid splits are fatal.
+fn periods(matches: &Args, label: &str) -> Option<Vec<Periods>> {
+    let splits = match matches.value_of("splits") {
+        Some(s) => Some(splits(s)?),
+        None => None,
+    };
+    let mut periods = BTreeSet::new();
+    if splits.is_none() || matches.occurrences_of(label) > 0 {
+        for v in matches.value_of(label).unwrap_or_default().split(',') {
+            let unit = v.trim_start_matches(|c: char| c.is_ascii_digit());
+            let count = match &v[..v.len() - unit.len()] {
+                "" => Some(1),
+                digits => digits.parse::<usize>().ok().filter(|n| *n > 0),
+            };
+            let duration = match unit {
+                "day" | "d" => Some(Duration::Day),
+                "week" | "w" => Some(Duration::Week),
+                "month" | "m" => Some(Duration::Month),
+                "quarter" | "q" => Some(Duration::Quarter),
+                "semester" | "s" => Some(Duration::Semester),
+                "year" | "y" => Some(Duration::Year),
+                _ => None,
+            };
+            match (duration, count) {
+                (Some(duration), Some(count)) => {
+                    periods.insert(Periods::Every(duration, count));
+                }
+                _ => {
+                    eprintln!("'{}' is not a valid duration", v);
+                    eprintln!("Expected one of 'day','week','month','quarter','semester','year' or 'd','w','m','q','s','y'");
+                    eprintln!("optionally preceded by a positive number, e.g. '2w'");
+                }
+            }
+        }
+    }
+    periods.extend(splits.map(Periods::Splits));
+    Some(periods.into_iter().collect())
+}
+
+/// Increasing dates separated by commas, errors are printed
+fn splits(s: &str) -> Option<Vec<Date>> {
+    let mut dates = Vec::new();
+    for v in s.split(',') {
+        match v.trim().parse::<Date>() {
+            Ok(date) => dates.push(date),
+            Err(()) => {
+                eprintln!("'{}' is not a valid date", v);
+                eprintln!("Expected YYYY-Mmm-DD, e.g. 2021-Mar-15");
+                return None;
+            }
+        }
+    }
+    if dates.len() < 2 {
+        eprintln!("At least two dates are needed to split periods");
+        return None;
+    }
+    if let Some(w) = dates.windows(2).find(|w| w[0] >= w[1]) {
+        eprintln!("Split dates should be increasing, {} is not after {}", w[1], w[0]);
+        return None;
+    }
+    Some(dates)
+}
+
+fn parse_arg_timeframe(args: &Args) -> Result<Interval<Date>, error::Record> {
+    match args.value_of("period") {
+        Some(value) => ledger::parse_period(value, Date::today()),
+        None => Ok(Interval::Unbounded),
+    }
 }
@@ -0,0 +1,117 @@
+//! Textual representation of amounts
+//!
+//! `Amount` itself always displays with the default format, so that tags expanded
+//! from templates and error messages do not depend on display settings.
+//! Tables, plots and reports are given their `AmountFmt` with `with_amount_fmt`.
+
+use crate::util::entry::Amount;
+
+/// Currency symbol and separators
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountFmt {
+    symbol: String,
+    /// whether the symbol comes before the number
+    prefix: bool,
+    decimal: char,
+    /// separator of groups of three digits
+    grouping: Option<char>,
+    cents: bool,
+}
+
+impl Default for AmountFmt {
+    /// `-1234.50€`
+    fn default() -> Self {
+        Self {
+            symbol: String::from("€"),
+            prefix: false,
+            decimal: '.',
+            grouping: None,
+            cents: true,
+        }
+    }
+}
+
+impl AmountFmt {
+    pub fn with_symbol<S>(mut self, s: S) -> Self
+    where
+        S: ToString,
+    {
+        self.symbol = s.to_string();
+        self
+    }
+
+    pub fn with_prefix(mut self, prefix: bool) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn with_decimal(mut self, decimal: char) -> Self {
+        self.decimal = decimal;
+        self
+    }
+
+    pub fn with_grouping(mut self, grouping: Option<char>) -> Self {
+        self.grouping = grouping;
+        self
+    }
+
+    /// Round to whole units if `cents` is false
+    pub fn with_cents(mut self, cents: bool) -> Self {
+        self.cents = cents;
+        self
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn format(&self, amount: Amount) -> String {
+        let cents = amount.0.unsigned_abs();
+        let (units, cents) = if self.cents {
+            (cents / 100, Some(cents % 100))
+        } else {
+            ((cents + 50) / 100, None)
+        };
+        let digits = units.to_string();
+        let mut number = String::new();
+        for (i, d) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                if let Some(sep) = self.grouping {
+                    number.push(sep);
+                }
+            }
+            number.push(d);
+        }
+        if let Some(cents) = cents {
+            number.push(self.decimal);
+            number.push_str(&format!("{:02}", cents));
+        }
+        let sign = if amount.0 < 0 && (units > 0 || cents.unwrap_or(0) > 0) { "-" } else { "" };
+        if self.prefix {
+            format!("{}{}{}", sign, self.symbol, number)
+        } else {
+            format!("{}{}{}", sign, number, self.symbol)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let dflt = AmountFmt::default();
+        assert_eq!(dflt.format(Amount(-123456)), "-1234.56€");
+        assert_eq!(dflt.format(Amount(5)), "0.05€");
+        let fr = AmountFmt::default().with_decimal(',').with_grouping(Some(' ')).with_symbol(" €");
+        assert_eq!(fr.format(Amount(123456789)), "1 234 567,89 €");
+        assert_eq!(fr.format(Amount(-99)), "-0,99 €");
+        let us = AmountFmt::default().with_symbol("$").with_prefix(true).with_grouping(Some(','));
+        assert_eq!(us.format(Amount(-100000)), "-$1,000.00");
+        let whole = us.with_cents(false);
+        assert_eq!(whole.format(Amount(123450)), "$1,235");
+        assert_eq!(whole.format(Amount(-40)), "$0");
+        assert_eq!(whole.format(Amount(-99950)), "-$1,000");
+    }
+}
@@ -5,7 +5,9 @@
 //! or years.
 //!
 //! They also support weekday calculations, and jumping to the boundaries of
-//! a time frame (see for example `start_of_week` or `end_of_month`)
+//! a time frame (see for example `start_of_week` or `end_of_month`).
+//! Weeks start on Monday and years on Jan 1st, a `Convention` gives the
+//! boundaries of weeks and years that start on another day or month.
 
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
@@ -17,6 +19,84 @@ pub use crate::util::{
     period::{Between, Interval},
 };
 
+/// First day of weeks and first month of (fiscal) years
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Convention {
+    week: Weekday,
+    year: Month,
+}
+
+impl Default for Convention {
+    /// Weeks from Monday, years from Jan 1st
+    fn default() -> Self {
+        Self {
+            week: Weekday::Mon,
+            year: Month::Jan,
+        }
+    }
+}
+
+impl Convention {
+    pub fn with_week_start(mut self, week: Weekday) -> Self {
+        self.week = week;
+        self
+    }
+
+    /// Years start on the 1st of `year`, quarters and semesters follow
+    pub fn with_year_start(mut self, year: Month) -> Self {
+        self.year = year;
+        self
+    }
+
+    pub fn week_start(&self) -> Weekday {
+        self.week
+    }
+
+    pub fn year_start(&self) -> Month {
+        self.year
+    }
+
+    /// First day of the week of `date`
+    pub fn start_of_week(&self, date: Date) -> Date {
+        date.start_of_week_on(self.week)
+    }
+
+    /// Last day of the week of `date`
+    pub fn end_of_week(&self, date: Date) -> Date {
+        self.start_of_week(date).jump_day(6)
+    }
+
+    /// First day of the quarter of `date`, one of the quarters starts with the year
+    pub fn start_of_quarter(&self, date: Date) -> Date {
+        date.start_of_cycle(self.year, 3)
+    }
+
+    /// Last day of the quarter of `date`
+    pub fn end_of_quarter(&self, date: Date) -> Date {
+        self.start_of_quarter(date).jump_month(3).prev()
+    }
+
+    /// First day of the semester of `date`, one of the semesters starts with the year
+    pub fn start_of_semester(&self, date: Date) -> Date {
+        date.start_of_cycle(self.year, 6)
+    }
+
+    /// Last day of the semester of `date`
+    pub fn end_of_semester(&self, date: Date) -> Date {
+        self.start_of_semester(date).jump_month(6).prev()
+    }
+
+    /// First day of the year of `date`
+    pub fn start_of_year(&self, date: Date) -> Date {
+        date.start_of_cycle(self.year, 12)
+    }
+
+    /// Last day of the year of `date`
+    pub fn end_of_year(&self, date: Date) -> Date {
+        self.start_of_year(date).jump_year(1).prev()
+    }
+}
+
 /// A date with day-precision
 ///
 /// Supports years in the range 1000..=9999, but weekday conversion
@@ -36,6 +116,42 @@ impl fmt::Display for Date {
     }
 }
 
+/// Inverse of `Display`: reads `YYYY-Mmm-DD`
+impl FromStr for Date {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut items = s.splitn(3, '-');
+        let year = items.next().ok_or(())?.parse::<usize>().map_err(|_| ())?;
+        let month = items.next().ok_or(())?.parse::<Month>()?;
+        let day = items.next().ok_or(())?.parse::<usize>().map_err(|_| ())?;
+        Self::from(year, month, day).map_err(|_| ())
+    }
+}
+
+/// Serialized as its `YYYY-Mmm-DD` representation
+#[cfg(feature = "serde")]
+impl serde::Serialize for Date {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Date {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(|()| {
+            serde::de::Error::custom(format!("'{}' is not a valid date YYYY-Mmm-DD", s))
+        })
+    }
+}
+
 /// Twelve months in the year, identified by their 3-letter abbreviations
 #[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, PartialOrd, Ord)]
 pub enum Month {
@@ -76,6 +192,9 @@ impl FromStr for Month {
 }
 
 impl Month {
+    /// Abbreviations in order, as read by `from_str`
+    pub const NAMES: [&'static str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
     /// Month directly succeeding the current one with wrapping
     pub fn next(self) -> Self {
         Self::from_isize((self as isize + 1) % 12).unwrap()
@@ -122,6 +241,9 @@ pub enum Weekday {
 }
 
 impl Weekday {
+    /// Abbreviations in order, as read by `from_str`
+    pub const NAMES: [&'static str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
     /// Weekday directly succeeding the current one with wrapping
     pub fn next(self) -> Self {
         Self::from_isize((self as isize + 1) % 7).unwrap()
@@ -133,6 +255,23 @@ impl Weekday {
     }
 }
 
+impl FromStr for Weekday {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        use Weekday::*;
+        Ok(match s {
+            "Mon" => Mon,
+            "Tue" => Tue,
+            "Wed" => Wed,
+            "Thu" => Thu,
+            "Fri" => Fri,
+            "Sat" => Sat,
+            "Sun" => Sun,
+            _ => return Err(()),
+        })
+    }
+}
+
 impl fmt::Display for Weekday {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
@@ -217,7 +356,9 @@ impl Date {
     /// This indexing is guaranteed consistent in the sense that
     /// for any date `d`,
     ///
-    ///     assert_eq!(d.index() + 1, d.next().index());
+    /// ```ignore
+    /// assert_eq!(d.index() + 1, d.next().index());
+    /// ```
     pub fn index(self) -> usize {
         let leaps = {
             let years = if self.month <= Month::Feb {
@@ -386,32 +527,55 @@ impl Date {
         }
     }
 
+    /// First day of the current quarter, in Jan, Apr, Jul or Oct
+    pub fn start_of_quarter(self) -> Self {
+        Convention::default().start_of_quarter(self)
+    }
+
+    /// Last day of the current quarter
+    pub fn end_of_quarter(self) -> Self {
+        Convention::default().end_of_quarter(self)
+    }
+
+    /// First day of the current semester, in Jan or Jul
+    pub fn start_of_semester(self) -> Self {
+        Convention::default().start_of_semester(self)
+    }
+
+    /// Last day of the current semester
+    pub fn end_of_semester(self) -> Self {
+        Convention::default().end_of_semester(self)
+    }
+
     /// Jan 1st of the current year
     pub fn start_of_year(self) -> Self {
-        Self {
-            day: 1,
-            month: Month::Jan,
-            ..self
-        }
+        Convention::default().start_of_year(self)
     }
 
     /// Dec 31st of the current year
     pub fn end_of_year(self) -> Self {
-        Self {
-            day: 31,
-            month: Month::Dec,
-            ..self
-        }
+        Convention::default().end_of_year(self)
     }
 
     /// First Monday before the current date
     pub fn start_of_week(self) -> Self {
-        self.jump_day(-(self.weekday() as isize))
+        Convention::default().start_of_week(self)
     }
 
     /// First Sunday after the current date
     pub fn end_of_week(self) -> Self {
-        self.jump_day(6 - self.weekday() as isize)
+        Convention::default().end_of_week(self)
+    }
+
+    /// First day of a cycle of `len` months, with one of the cycles starting in `first`
+    fn start_of_cycle(self, first: Month, len: isize) -> Self {
+        let offset = (self.month as isize - first as isize).rem_euclid(len);
+        Self { day: 1, ..self }.jump_month(-offset)
+    }
+
+    /// First `first` before the current date
+    fn start_of_week_on(self, first: Weekday) -> Self {
+        self.jump_day(-((self.weekday() as isize - first as isize).rem_euclid(7)))
     }
 
     /// Set maximum value for day
@@ -425,12 +589,12 @@ impl Date {
 }
 
 fn is_leap(year: u16) -> bool {
-    if year % 400 == 0 {
+    if year.is_multiple_of(400) {
         true
-    } else if year % 100 == 0 {
+    } else if year.is_multiple_of(100) {
         false
     } else {
-        year % 4 == 0
+        year.is_multiple_of(4)
     }
 }
 
@@ -725,5 +889,62 @@ mod test {
         day!(dt!(2000-Jan-5) => Wed);
         assert_eq!(dt!(2000-Jan-5).start_of_week(), dt!(2000-Jan-3));
         assert_eq!(dt!(2000-Jan-5).end_of_week(), dt!(2000-Jan-9));
+        assert_eq!(dt!(2020-Mar-31).start_of_quarter(), dt!(2020-Jan-1));
+        assert_eq!(dt!(2020-Aug-12).start_of_quarter(), dt!(2020-Jul-1));
+        assert_eq!(dt!(2020-Jan-1).end_of_quarter(), dt!(2020-Mar-31));
+        assert_eq!(dt!(2020-Nov-30).end_of_quarter(), dt!(2020-Dec-31));
+        assert_eq!(dt!(2020-Jun-30).start_of_semester(), dt!(2020-Jan-1));
+        assert_eq!(dt!(2020-Jul-1).start_of_semester(), dt!(2020-Jul-1));
+        assert_eq!(dt!(2020-Feb-29).end_of_semester(), dt!(2020-Jun-30));
+        assert_eq!(dt!(2020-Oct-3).end_of_semester(), dt!(2020-Dec-31));
+    }
+
+    #[test]
+    fn shifted_boundaries() {
+        assert_eq!(dt!(2020-Sep-1).start_of_cycle(Sep, 12), dt!(2020-Sep-1));
+        assert_eq!(dt!(2020-Aug-31).start_of_cycle(Sep, 12), dt!(2019-Sep-1));
+        assert_eq!(dt!(2021-Feb-14).start_of_cycle(Sep, 12), dt!(2020-Sep-1));
+        assert_eq!(dt!(2021-Feb-14).start_of_cycle(Sep, 6), dt!(2020-Sep-1));
+        assert_eq!(dt!(2021-Mar-1).start_of_cycle(Sep, 6), dt!(2021-Mar-1));
+        assert_eq!(dt!(2020-Dec-25).start_of_cycle(Sep, 3), dt!(2020-Dec-1));
+        assert_eq!(dt!(2020-Nov-30).start_of_cycle(Sep, 3), dt!(2020-Sep-1));
+        assert_eq!(dt!(2020-Nov-30).start_of_cycle(Jan, 3), dt!(2020-Oct-1));
+        // 2000-Jan-5 is a Wednesday
+        assert_eq!(dt!(2000-Jan-5).start_of_week_on(Mon), dt!(2000-Jan-3));
+        assert_eq!(dt!(2000-Jan-5).start_of_week_on(Sun), dt!(2000-Jan-2));
+        assert_eq!(dt!(2000-Jan-5).start_of_week_on(Wed), dt!(2000-Jan-5));
+        assert_eq!(dt!(2000-Jan-5).start_of_week_on(Thu), dt!(1999-Dec-30));
+        for i in 1..15 {
+            day!(dt!(2020-Jan-i).start_of_week_on(Sun) => Sun);
+        }
+    }
+
+    #[test]
+    fn conventions() {
+        let academic = Convention::default().with_week_start(Sun).with_year_start(Sep);
+        for i in 1..15 {
+            day!(academic.start_of_week(dt!(2020-Jan-i)) => Sun);
+            day!(academic.end_of_week(dt!(2020-Jan-i)) => Sat);
+        }
+        assert_eq!(academic.start_of_year(dt!(2021-Feb-14)), dt!(2020-Sep-1));
+        assert_eq!(academic.end_of_year(dt!(2021-Feb-14)), dt!(2021-Aug-31));
+        assert_eq!(academic.start_of_semester(dt!(2021-Feb-14)), dt!(2020-Sep-1));
+        assert_eq!(academic.end_of_semester(dt!(2021-Mar-1)), dt!(2021-Aug-31));
+        assert_eq!(academic.start_of_quarter(dt!(2020-Nov-30)), dt!(2020-Sep-1));
+        assert_eq!(academic.end_of_quarter(dt!(2020-Dec-1)), dt!(2021-Feb-28));
+        // the default is the same as the methods of `Date`
+        let dflt = Convention::default();
+        assert_eq!(dflt.start_of_year(dt!(2021-Feb-14)), dt!(2021-Feb-14).start_of_year());
+        assert_eq!(dflt.end_of_week(dt!(2000-Jan-5)), dt!(2000-Jan-5).end_of_week());
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("2020-Mar-05".parse::<Date>(), Ok(dt!(2020-Mar-5)));
+        assert_eq!("2020-Mar-5".parse::<Date>(), Ok(dt!(2020-Mar-5)));
+        assert_eq!(dt!(2021-Dec-31).to_string().parse::<Date>(), Ok(dt!(2021-Dec-31)));
+        assert_eq!("2021-Feb-29".parse::<Date>(), Err(()));
+        assert_eq!("2021-03-01".parse::<Date>(), Err(()));
+        assert_eq!("2021-Mar".parse::<Date>(), Err(()));
     }
 }
@@ -5,7 +5,10 @@ use std::str::FromStr;
 
 use num_derive::FromPrimitive;
 
-use crate::util::date::{Between, Date};
+use crate::util::{
+    currency::AmountFmt,
+    date::{Between, Convention, Date},
+};
 
 /// Contents of entries
 pub mod fields {
@@ -13,22 +16,23 @@ pub mod fields {
 }
 
 /// A quantity of money with cent precision
+///
+/// Serialized as an integer number of cents
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Amount(pub isize);
 
 /// A label for an expense
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Tag(pub String);
 
+/// With the default `AmountFmt`
 impl fmt::Display for Amount {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}{}.{:02}€",
-            if self.0 >= 0 { "" } else { "-" },
-            self.0.abs() / 100,
-            (self.0 % 100).abs()
-        )
+        write!(f, "{}", AmountFmt::default().format(*self))
     }
 }
 
@@ -44,17 +48,51 @@ impl fmt::Display for Tag {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "EntryFields"))]
 pub struct Entry {
     value: Amount,
+    #[cfg_attr(feature = "serde", serde(rename = "category"))]
     cat: Category,
     period: Between<Date>,
     /// cached length of the period for performance
+    #[cfg_attr(feature = "serde", serde(skip))]
     length: usize,
     tag: Option<Tag>,
 }
 
+/// Deserialization helper for `Entry` that checks the period
+/// and recomputes the cached length
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct EntryFields {
+    value: Amount,
+    category: Category,
+    period: Between<Date>,
+    tag: Option<Tag>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<EntryFields> for Entry {
+    type Error = String;
+    fn try_from(fields: EntryFields) -> Result<Self, String> {
+        let period = fields.period;
+        if period.0 > period.1 {
+            return Err(format!("period {}..{} is empty", period.0, period.1));
+        }
+        Ok(Self {
+            value: fields.value,
+            cat: fields.category,
+            period,
+            length: period.1.index() - period.0.index() + 1,
+            tag: fields.tag,
+        })
+    }
+}
+
 /// Kinds of expenses
 #[derive(Debug, Clone, Copy, FromPrimitive, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Category {
     Salary,
     Home,
@@ -80,6 +118,8 @@ pub enum Duration {
     Day,
     Week,
     Month,
+    Quarter,
+    Semester,
     Year,
 }
 
@@ -89,9 +129,28 @@ impl Duration {
             Duration::Day => "Daily",
             Duration::Week => "Weekly",
             Duration::Month => "Monthly",
+            Duration::Quarter => "Quarterly",
+            Duration::Semester => "Half-yearly",
             Duration::Year => "Yearly",
         }
     }
+
+    /// `count` times the duration in words, e.g. `2 weeks`
+    pub fn text_count(self, count: usize) -> String {
+        let unit = match self {
+            Duration::Day => "day",
+            Duration::Week => "week",
+            Duration::Month => "month",
+            Duration::Quarter => "quarter",
+            Duration::Semester => "semester",
+            Duration::Year => "year",
+        };
+        if count == 1 {
+            format!("1 {}", unit)
+        } else {
+            format!("{} {}s", count, unit)
+        }
+    }
 }
 
 /// Position of `Span` relative to reference date
@@ -108,10 +167,50 @@ impl Category {
     pub const COUNT: usize = 8;
 
     pub fn sign(self) -> bool {
+        matches!(self, Category::Salary)
+    }
+
+    /// Name in `.bil` files, inverse of `from_str`
+    pub fn keyword(self) -> &'static str {
         use Category::*;
         match self {
-            Salary => true,
-            _ => false,
+            Salary => "Pay",
+            Food => "Food",
+            Tech => "Tech",
+            Movement => "Mov",
+            School => "Pro",
+            Cleaning => "Clean",
+            Home => "Home",
+            Fun => "Fun",
+        }
+    }
+}
+
+impl Duration {
+    /// Name in `.bil` files, inverse of `from_str`
+    pub fn keyword(self) -> &'static str {
+        use Duration::*;
+        match self {
+            Day => "Day",
+            Week => "Week",
+            Month => "Month",
+            Quarter => "Quarter",
+            Semester => "Semester",
+            Year => "Year",
+        }
+    }
+}
+
+impl Window {
+    /// Name in `.bil` files, inverse of `from_str`
+    pub fn keyword(self) -> &'static str {
+        use Window::*;
+        match self {
+            Current => "Curr",
+            Posterior => "Post",
+            Anterior => "Ante",
+            Precedent => "Pred",
+            Successor => "Succ",
         }
     }
 }
@@ -214,21 +313,37 @@ impl Entry {
     pub fn period(&self) -> Between<Date> {
         self.period
     }
-}
 
-impl fmt::Display for Entry {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let value = format!("{}", self.value);
+    pub fn tag(&self) -> Option<&Tag> {
+        self.tag.as_ref()
+    }
+
+    /// Same as `Display`, with the amount in format `fmt`
+    pub fn text(&self, fmt: &AmountFmt) -> String {
+        let value = fmt.format(self.value);
         let padding = " ".repeat(10_usize.saturating_sub(value.len()));
-        write!(
-            f,
+        let mut text = format!(
             "{}..{}: \t{}{}\t ({:?}",
             self.period.0, self.period.1, padding, value, self.cat
-        )?;
+        );
         if let Some(t) = &self.tag {
-            write!(f, "/{}", t.0)?;
+            text.push_str(&format!("/{}", t.0));
         }
-        write!(f, ")")
+        text.push(')');
+        text
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text(&AmountFmt::default()))
+    }
+}
+
+/// Same syntax as in `.bil` files, e.g. `Month<Post> 2`
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}<{}> {}", self.duration.keyword(), self.window.keyword(), self.count)
     }
 }
 
@@ -241,8 +356,9 @@ impl Span {
         }
     }
 
-    /// Use reference date to create a range of dates
-    pub fn period(&self, dt: Date) -> Between<Date> {
+    /// Use reference date to create a range of dates,
+    /// with the boundaries of weeks, quarters, semesters and years of `conv`
+    pub fn period(&self, dt: Date, conv: Convention) -> Between<Date> {
         use Duration::*;
         use Window::*;
         let nb = self.count as isize;
@@ -251,15 +367,15 @@ impl Span {
             (Day, Successor) => (dt.next(), dt.jump_day(nb)),
             (Day, Anterior) => (dt.jump_day(-nb).next(), dt),
             (Day, _) => (dt, dt.jump_day(nb).prev()),
-            (Week, Current) => (dt.start_of_week(), dt.end_of_week().jump_day(7 * (nb - 1))),
+            (Week, Current) => (conv.start_of_week(dt), conv.end_of_week(dt).jump_day(7 * (nb - 1))),
             (Week, Anterior) => (dt.jump_day(-7 * nb).next(), dt),
             (Week, Posterior) => (dt, dt.jump_day(7 * nb).prev()),
             (Week, Precedent) => {
-                let d = dt.start_of_week();
+                let d = conv.start_of_week(dt);
                 (d.jump_day(-7 * nb), d.prev())
             }
             (Week, Successor) => {
-                let d = dt.end_of_week();
+                let d = conv.end_of_week(dt);
                 (d.next(), d.jump_day(7 * nb))
             }
             (Month, Current) => (dt.start_of_month(), dt.jump_month(nb - 1).end_of_month()),
@@ -273,15 +389,37 @@ impl Span {
                 let d = dt.end_of_month();
                 (d.next(), d.jump_month(nb))
             }
-            (Year, Current) => (dt.start_of_year(), dt.end_of_year().jump_year(nb - 1)),
+            (Quarter, Current) => (conv.start_of_quarter(dt), conv.end_of_quarter(dt.jump_month(3 * (nb - 1)))),
+            (Quarter, Posterior) => (dt, dt.jump_month(3 * nb).cap_day(dt.day())),
+            (Quarter, Anterior) => (dt.jump_month(-3 * nb).next(), dt),
+            (Quarter, Precedent) => {
+                let d = conv.start_of_quarter(dt);
+                (d.jump_month(-3 * nb), d.prev())
+            }
+            (Quarter, Successor) => {
+                let d = conv.end_of_quarter(dt).next();
+                (d, d.jump_month(3 * nb).prev())
+            }
+            (Semester, Current) => (conv.start_of_semester(dt), conv.end_of_semester(dt.jump_month(6 * (nb - 1)))),
+            (Semester, Posterior) => (dt, dt.jump_month(6 * nb).cap_day(dt.day())),
+            (Semester, Anterior) => (dt.jump_month(-6 * nb).next(), dt),
+            (Semester, Precedent) => {
+                let d = conv.start_of_semester(dt);
+                (d.jump_month(-6 * nb), d.prev())
+            }
+            (Semester, Successor) => {
+                let d = conv.end_of_semester(dt).next();
+                (d, d.jump_month(6 * nb).prev())
+            }
+            (Year, Current) => (conv.start_of_year(dt), conv.end_of_year(dt).jump_year(nb - 1)),
             (Year, Posterior) => (dt, dt.jump_year(nb).cap_day(dt.day())),
             (Year, Anterior) => (dt.jump_year(-nb).next(), dt),
             (Year, Successor) => {
-                let d = dt.end_of_year();
+                let d = conv.end_of_year(dt);
                 (d.next(), d.jump_year(nb))
             }
             (Year, Precedent) => {
-                let d = dt.start_of_year();
+                let d = conv.start_of_year(dt);
                 (d.jump_year(-nb), d.prev())
             }
         };
@@ -315,6 +453,8 @@ impl FromStr for Duration {
             "Day" => Day,
             "Week" => Week,
             "Month" => Month,
+            "Quarter" => Quarter,
+            "Semester" => Semester,
             "Year" => Year,
             _ => return Err(()),
         })
@@ -341,7 +481,7 @@ impl FromStr for Window {
 mod test {
     use num_traits::FromPrimitive;
     use super::{Duration::*, Window::*, *};
-    use crate::util::date::{Date, Month::*};
+    use crate::util::date::{Date, Month::*, Weekday};
 
     #[test]
     fn count_categories() {
@@ -368,7 +508,7 @@ mod test {
 
     macro_rules! check {
         ( $date:expr, $span:expr, $start:expr, $end:expr ) => {
-            assert_eq!($span.period($date), Between($start, $end));
+            assert_eq!($span.period($date, Convention::default()), Between($start, $end));
         };
     }
 
@@ -510,6 +650,98 @@ mod test {
         );
     }
 
+    #[test]
+    fn quarter_jumps() {
+        check!(
+            dt!(2020-Feb-14),
+            span!(Quarter<Current>1),
+            dt!(2020-Jan-1),
+            dt!(2020-Mar-31)
+        );
+        check!(
+            dt!(2020-Nov-30),
+            span!(Quarter<Current>2),
+            dt!(2020-Oct-1),
+            dt!(2021-Mar-31)
+        );
+        check!(
+            dt!(2020-Nov-30),
+            span!(Quarter<Posterior>1),
+            dt!(2020-Nov-30),
+            dt!(2021-Feb-28)
+        );
+        check!(
+            dt!(2020-Jan-15),
+            span!(Quarter<Posterior>2),
+            dt!(2020-Jan-15),
+            dt!(2020-Jul-14)
+        );
+        check!(
+            dt!(2020-May-31),
+            span!(Quarter<Anterior>1),
+            dt!(2020-Mar-1),
+            dt!(2020-May-31)
+        );
+        check!(
+            dt!(2020-Aug-15),
+            span!(Quarter<Precedent>2),
+            dt!(2020-Jan-1),
+            dt!(2020-Jun-30)
+        );
+        check!(
+            dt!(2020-Aug-15),
+            span!(Quarter<Successor>1),
+            dt!(2020-Oct-1),
+            dt!(2020-Dec-31)
+        );
+        check!(
+            dt!(2020-Dec-31),
+            span!(Quarter<Successor>3),
+            dt!(2021-Jan-1),
+            dt!(2021-Sep-30)
+        );
+    }
+
+    #[test]
+    fn semester_jumps() {
+        check!(
+            dt!(2020-Sep-3),
+            span!(Semester<Current>1),
+            dt!(2020-Jul-1),
+            dt!(2020-Dec-31)
+        );
+        check!(
+            dt!(2020-Mar-3),
+            span!(Semester<Current>3),
+            dt!(2020-Jan-1),
+            dt!(2021-Jun-30)
+        );
+        check!(
+            dt!(2019-Aug-31),
+            span!(Semester<Posterior>1),
+            dt!(2019-Aug-31),
+            dt!(2020-Feb-29)
+        );
+        check!(
+            dt!(2020-Apr-1),
+            span!(Semester<Anterior>1),
+            dt!(2019-Oct-2),
+            dt!(2020-Apr-1)
+        );
+        check!(
+            dt!(2020-Jul-1),
+            span!(Semester<Precedent>1),
+            dt!(2020-Jan-1),
+            dt!(2020-Jun-30)
+        );
+        check!(
+            dt!(2020-Jun-30),
+            span!(Semester<Successor>2),
+            dt!(2020-Jul-1),
+            dt!(2021-Jun-30)
+        );
+    }
+
     #[test]
     fn year_jumps() {
         check!(
@@ -565,6 +797,25 @@ mod test {
         }};
     }
 
+    #[test]
+    fn academic_jumps() {
+        let conv = Convention::default().with_week_start(Weekday::Sun).with_year_start(Sep);
+        let check = |date, span: Span, start, end| assert_eq!(span.period(date, conv), Between(start, end));
+        // 2021-Feb-14 is a Sunday
+        check(dt!(2021-Feb-14), span!(Week<Current>1), dt!(2021-Feb-14), dt!(2021-Feb-20));
+        check(dt!(2021-Feb-13), span!(Week<Precedent>1), dt!(2021-Jan-31), dt!(2021-Feb-6));
+        check(dt!(2021-Feb-14), span!(Year<Current>1), dt!(2020-Sep-1), dt!(2021-Aug-31));
+        check(dt!(2020-Aug-31), span!(Year<Successor>1), dt!(2020-Sep-1), dt!(2021-Aug-31));
+        check(dt!(2020-Sep-1), span!(Year<Precedent>1), dt!(2019-Sep-1), dt!(2020-Aug-31));
+        check(dt!(2021-Feb-14), span!(Semester<Current>1), dt!(2020-Sep-1), dt!(2021-Feb-28));
+        check(dt!(2021-Feb-14), span!(Semester<Successor>1), dt!(2021-Mar-1), dt!(2021-Aug-31));
+        check(dt!(2020-Nov-30), span!(Quarter<Current>2), dt!(2020-Sep-1), dt!(2021-Feb-28));
+        check(dt!(2020-Nov-30), span!(Quarter<Precedent>1), dt!(2020-Jun-1), dt!(2020-Aug-31));
+        // months and relative windows do not depend on the convention
+        check(dt!(2021-Feb-14), span!(Month<Current>1), dt!(2021-Feb-1), dt!(2021-Feb-28));
+        check(dt!(2021-Feb-14), span!(Year<Posterior>1), dt!(2021-Feb-14), dt!(2022-Feb-13));
+    }
+
     #[test]
     fn intersections() {
         assert_eq!(
@@ -583,7 +834,7 @@ mod test {
         );
         {
             let entry = bogus!(1763, dt!(2020-Mar-13), dt!(2020-Sep-27));
-            let sections = vec![
+            let sections = [
                 dt!(2020-Mar-13),
                 dt!(2020-Apr-5),
                 dt!(2020-Jun-30),
@@ -595,4 +846,27 @@ mod test {
             assert_eq!(entry.value, splits.map(|e| e.value).sum())
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let entry = Entry::from(
+            Amount(-1250),
+            Category::Food,
+            Between(dt!(2021-Mar-15), dt!(2021-Mar-21)),
+            Tag("Groceries".to_string()),
+        );
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(
+            json,
+            r#"{"value":-1250,"category":"Food","period":["2021-Mar-15","2021-Mar-21"],"tag":"Groceries"}"#
+        );
+        let back: Entry = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.length, 7);
+        assert_eq!(back.to_string(), entry.to_string());
+        let reversed = r#"{"value":0,"category":"Fun","period":["2021-Mar-15","2021-Mar-1"],"tag":null}"#;
+        assert!(serde_json::from_str::<Entry>(reversed).is_err());
+        let invalid = r#"{"value":0,"category":"Fun","period":["2021-Feb-30","2021-Mar-1"],"tag":null}"#;
+        assert!(serde_json::from_str::<Entry>(invalid).is_err());
+    }
 }
@@ -2,6 +2,7 @@
 //!
 //! Date management, entry definition and aggregation
 
+pub mod currency;
 pub mod date;
 pub mod entry;
 pub mod period;
@@ -16,6 +16,7 @@ impl Minimax for i64 {
 
 /// `Period(a, b)` is the range of dates from `a` to `b` inclusive
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Between<T>(pub T, pub T);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -35,7 +36,7 @@ where
         let (start, end) = match self {
             Interval::Between(start, end) => (start, end),
             Interval::After(start) => (start, T::MAX),
-            Interval::Before(end) => (T::MAX, end),
+            Interval::Before(end) => (T::MIN, end),
             Interval::Empty => (T::MAX, T::MIN),
             Interval::Unbounded => (T::MIN, T::MAX),
         };
@@ -335,11 +336,11 @@ pub fn validate_partial_period(
     match inner.as_rule() {
         Rule::period_after => {
             let trunc =
-                validate_partial_date(path, errs, inner.into_inner().into_iter().next().unwrap())?;
+                validate_partial_date(path, errs, inner.into_inner().next().unwrap())?;
             Some(Interval::After(trunc))
         }
         Rule::period_before => {
-            let end = inner.into_inner().into_iter().next();
+            let end = inner.into_inner().next();
             match end {
                 Some(end) => {
                     let trunc = validate_partial_date(path, errs, end)?;
@@ -481,8 +482,7 @@ impl PartialDate {
 #[cfg(test)]
 #[rustfmt::skip]
 mod test {
-    use crate::lib::date::{Month::*, *};
-    use super::*;
+    use crate::util::date::{Month::*, *};
 
     macro_rules! dt {
         ( $y:tt - $m:tt - $d:tt ) => {{
@@ -515,13 +515,25 @@ mod test {
         pp!(dt!(2020-Jan-3), dt!(2023-Feb-28) => "2020-Jan-3..2023-Feb");
     }
 
+    #[test]
+    fn period_bounds() {
+        use crate::util::period::Minimax;
+        let (start, end) = (dt!(2020-Jan-15), dt!(2021-Mar-17));
+        assert_eq!(Interval::Between(start, end).into_between(), Between(start, end));
+        assert_eq!(Interval::After(start).into_between(), Between(start, Date::MAX));
+        assert_eq!(Interval::Before(end).into_between(), Between(Date::MIN, end));
+        assert_eq!(Interval::<Date>::Unbounded.into_between(), Between(Date::MIN, Date::MAX));
+        let Between(empty_start, empty_end) = Interval::<Date>::Empty.into_between();
+        assert!(empty_start > empty_end);
+    }
+
     macro_rules! ps {
         ( $s:tt, $b:tt, $res:tt ) => {{
             let mut err = crate::load::error::Record::new();
             match Interval::parse("raw", &mut err, $s).map(|pp| pp.make(&mut err, &("", pest::Span::new("", 0, 0).unwrap()), dt!(2021-Feb-1))).flatten() {
                 Some(period) => {
-                    if !$b { panic!("{} instead of a failure\nHelp: this should be rejected", period.into_period()); }
-                    assert_eq!(&format!("{}", period.into_period()), $res);
+                    if !$b { panic!("{} instead of a failure\nHelp: this should be rejected", period.into_between()); }
+                    assert_eq!(&format!("{}", period.into_between()), $res);
                 }
                 None => {
                     if $b { panic!("{} instead of a success\nHelp: this should be accepted", err); }
@@ -1,11 +1,12 @@
 use std::ops;
 
 use crate::util::{
-    date::{Between, Date},
+    date::{Between, Convention, Date},
     entry::{Amount, Category, Duration, Entry},
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Summary {
     /// Period of relevance added entries are to be intersected with
     period: Between<Date>,
@@ -13,6 +14,9 @@ pub struct Summary {
     total: Amount,
     /// Subtotals per expense kind
     categories: [Amount; Category::COUNT],
+    /// Period cut short by the bounds of the calendar
+    #[cfg_attr(feature = "serde", serde(default))]
+    partial: bool,
 }
 
 impl Summary {
@@ -22,6 +26,7 @@ impl Summary {
             period,
             total: Amount(0),
             categories: [Amount(0); Category::COUNT],
+            partial: false,
         }
     }
 
@@ -47,6 +52,11 @@ impl Summary {
     pub fn total(&self) -> Amount {
         self.total
     }
+
+    /// Whether the period is only part of a whole day, week, month, quarter, semester or year
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
 }
 
 impl ops::AddAssign<&Entry> for Summary {
@@ -64,6 +74,15 @@ impl ops::AddAssign<&Entry> for Summary {
     }
 }
 
+/// Where the steps of a calendar start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// on the first day of the week, month, quarter, semester or year that contains the start of the period
+    Natural,
+    /// on the first day of the period
+    Start,
+}
+
 /// A collection of disjoint ordered summaries
 #[derive(Debug)]
 pub struct Calendar {
@@ -73,6 +92,7 @@ pub struct Calendar {
 impl Calendar {
     /// Construct from an _increasing_ iterator of dates
     /// Periods[d1, d2, d3, ..., dn] -> Calendar[d1..d2, d2..d3, ..., dn-1..dn]
+    #[allow(clippy::should_implement_trait)]
     pub fn from_iter<I>(mut splits: I) -> Self
     where
         I: Iterator<Item = Date>,
@@ -114,12 +134,43 @@ impl Calendar {
                 Duration::Day => date.jump_day(count as isize),
                 Duration::Week => date.jump_day(count as isize * 7),
                 Duration::Month => date.jump_month(count as isize),
+                Duration::Quarter => date.jump_month(count as isize * 3),
+                Duration::Semester => date.jump_month(count as isize * 6),
                 Duration::Year => date.jump_year(count as isize),
             };
             Some(next)
         })
     }
 
+    /// Construct from a standardized span step generator, with steps that start
+    /// on the boundaries of weeks, months, quarters, semesters or years
+    ///
+    /// The first and last summaries are restricted to `period`, and then marked as partial
+    pub fn from_aligned(period: Between<Date>, duration: Duration, count: usize, conv: Convention) -> Self {
+        let start = match duration {
+            Duration::Day => period.0,
+            Duration::Week => conv.start_of_week(period.0),
+            Duration::Month => period.0.start_of_month(),
+            Duration::Quarter => conv.start_of_quarter(period.0),
+            Duration::Semester => conv.start_of_semester(period.0),
+            Duration::Year => conv.start_of_year(period.0),
+        };
+        let mut cal = Self::from_spacing(Between(start, period.1), duration, count);
+        if let Some(first) = cal.items.first_mut() {
+            if first.period.0 < period.0 {
+                first.period.0 = period.0;
+                first.partial = true;
+            }
+        }
+        if let Some(last) = cal.items.last_mut() {
+            if last.period.1 > period.1 {
+                last.period.1 = period.1;
+                last.partial = true;
+            }
+        }
+        cal
+    }
+
     /// Find index that contains `target`
     ///
     /// `start` is large, `end` is strict
@@ -151,7 +202,8 @@ impl Calendar {
         }
     }
 
-    fn dichotomy(&self, period: Between<Date>) -> Option<&[Summary]> {
+    /// Summaries that overlap with `period`
+    pub fn dichotomy(&self, period: Between<Date>) -> Option<&[Summary]> {
         let (start, end) = self.dichotomy_idx(period)?;
         Some(&self.items[start..=end])
     }
@@ -169,7 +221,7 @@ impl Calendar {
                     *summary += item;
                 }
             } else {
-                println!("Empty range for {}", item.period());
+                eprintln!("Empty range for {}", item.period());
             }
         }
     }
@@ -183,9 +235,8 @@ impl Calendar {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::lib::{
+    use crate::util::{
         date::{Date, Month::*},
-        entry::Duration::*,
     };
 
     macro_rules! dt {
@@ -245,4 +296,51 @@ mod test {
         assert!(ans[ans.len() - 1].period.0 <= dt!(2020-Mar-18));
         assert!(ans[ans.len() - 1].period.1 >= dt!(2020-Mar-18));
     }
+
+    #[test]
+    fn alignment() {
+        let dflt = Convention::default();
+        let period = Between(dt!(2020-Sep-17), dt!(2021-Feb-3));
+        let cal = Calendar::from_aligned(period, Duration::Month, 1, dflt);
+        let periods = cal.items.iter().map(|s| (s.period, s.partial)).collect::<Vec<_>>();
+        assert_eq!(periods.len(), 6);
+        assert_eq!(periods[0], (Between(dt!(2020-Sep-17), dt!(2020-Sep-30)), true));
+        assert_eq!(periods[1], (Between(dt!(2020-Oct-1), dt!(2020-Oct-31)), false));
+        assert_eq!(periods[5], (Between(dt!(2021-Feb-1), dt!(2021-Feb-3)), true));
+        // 2020-Sep-17 is a Thursday
+        let cal = Calendar::from_aligned(period, Duration::Week, 1, dflt);
+        assert_eq!(cal.items[0].period, Between(dt!(2020-Sep-17), dt!(2020-Sep-20)));
+        assert_eq!(cal.items[1].period, Between(dt!(2020-Sep-21), dt!(2020-Sep-27)));
+        let cal = Calendar::from_aligned(period, Duration::Year, 1, dflt);
+        assert!(cal.items.iter().all(|s| s.partial));
+        assert_eq!(cal.items[1].period, Between(dt!(2021-Jan-1), dt!(2021-Feb-3)));
+        let cal = Calendar::from_aligned(period, Duration::Quarter, 1, dflt);
+        assert_eq!(cal.items.len(), 3);
+        assert_eq!(cal.items[0].period, Between(dt!(2020-Sep-17), dt!(2020-Sep-30)));
+        assert_eq!(cal.items[1].period, Between(dt!(2020-Oct-1), dt!(2020-Dec-31)));
+        // already aligned
+        let cal = Calendar::from_aligned(Between(dt!(2020-Jan-1), dt!(2020-Dec-31)), Duration::Month, 1, dflt);
+        assert_eq!(cal.items.len(), 12);
+        assert!(cal.items.iter().all(|s| !s.partial));
+        let cal = Calendar::from_spacing(period, Duration::Month, 1);
+        assert_eq!(cal.items[0].period, Between(dt!(2020-Sep-17), dt!(2020-Oct-16)));
+    }
+
+    #[test]
+    fn academic_alignment() {
+        use crate::util::date::Weekday;
+        let conv = Convention::default().with_week_start(Weekday::Sun).with_year_start(Sep);
+        let period = Between(dt!(2020-Sep-17), dt!(2022-Feb-3));
+        let cal = Calendar::from_aligned(period, Duration::Year, 1, conv);
+        let periods = cal.items.iter().map(|s| (s.period, s.partial)).collect::<Vec<_>>();
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0], (Between(dt!(2020-Sep-17), dt!(2021-Aug-31)), true));
+        assert_eq!(periods[1], (Between(dt!(2021-Sep-1), dt!(2022-Feb-3)), true));
+        let cal = Calendar::from_aligned(period, Duration::Semester, 1, conv);
+        assert_eq!(cal.items[1].period, Between(dt!(2021-Mar-1), dt!(2021-Aug-31)));
+        // 2020-Sep-17 is a Thursday
+        let cal = Calendar::from_aligned(period, Duration::Week, 1, conv);
+        assert_eq!(cal.items[0].period, Between(dt!(2020-Sep-17), dt!(2020-Sep-19)));
+        assert_eq!(cal.items[1].period, Between(dt!(2020-Sep-20), dt!(2020-Sep-26)));
+    }
 }
@@ -0,0 +1,175 @@
+//! Behavior of the `billig` executable: what ends up on stdout and stderr
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Run billig from the root of the repository, without any user configuration
+fn billig(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_billig"))
+        .args(args)
+        .args(["--color", "never"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("XDG_CONFIG_HOME", scratch("config"))
+        .output()
+        .unwrap()
+}
+
+/// Fresh path in the temporary directory
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("billig-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[test]
+fn import_warnings_on_stderr() {
+    let statement = scratch("bad.csv");
+    std::fs::write(
+        &statement,
+        "Date;Libelle;Debit;Credit\n01/03/2021;PRLV LOYER MARS;650,00;\n32/03/2021;CB CINEMA;11,00;\n",
+    )
+    .unwrap();
+    let out = billig(&["import", statement.to_str().unwrap(), "--rules", "examples/import/rules.bil"]);
+    assert!(out.status.success());
+    assert!(text(&out.stderr).contains("Warning"));
+    let imported = scratch("imported.bil");
+    std::fs::write(&imported, &out.stdout).unwrap();
+    assert!(text(&out.stdout).contains("Rent"));
+    let check = billig(&["check", imported.to_str().unwrap()]);
+    assert!(check.status.success(), "{}", text(&check.stdout));
+}
+
+#[test]
+fn report_warnings_on_stderr() {
+    for output in ["json", "csv", "tsv"] {
+        let out = billig(&["report", "examples/failures/unused.bil", "--output", output]);
+        assert!(out.status.success());
+        assert!(text(&out.stderr).contains("Unused argument"));
+        assert!(!text(&out.stdout).contains("Unused argument"), "{}", output);
+    }
+    let out = billig(&["report", "examples/failures/unused.bil", "--output", "json"]);
+    let tables: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(tables[0]["title"], "Monthly");
+    // named as in .bil files and in the [colors] of billig.toml
+    assert!(tables[0]["rows"][0]["Pay"].is_number());
+}
+
+#[test]
+fn export_warnings_on_stderr() {
+    let out = billig(&["export", "examples/failures/unused.bil"]);
+    assert!(out.status.success());
+    assert!(text(&out.stderr).contains("Unused argument"));
+    let journal = text(&out.stdout);
+    assert!(journal.starts_with("2020-09-01 "), "{}", journal);
+    assert!(!journal.contains("Unused argument"));
+}
+
+#[test]
+fn plot_stdout_is_svg() {
+    let out = billig(&["plot", "examples/failures/unused.bil", "--stdout"]);
+    assert!(out.status.success());
+    assert!(text(&out.stderr).contains("Unused argument"));
+    let svg = text(&out.stdout);
+    assert!(svg.starts_with("<svg"), "{}", svg);
+    assert!(svg.trim_end().ends_with("</svg>"));
+    // a single day with no amount still has a scale
+    assert!(!svg.contains("NaN"));
+}
+
+#[test]
+fn export_amortize_beancount() {
+    let out = billig(&["export", "examples/overview.bil", "--format", "beancount", "--amortize", "month"]);
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+    assert!(text(&out.stderr).contains("--amortize"));
+    let out = billig(&["export", "examples/overview.bil", "--format", "beancount", "--amortize", "none"]);
+    assert!(out.status.success());
+}
+
+#[test]
+fn cents_override_config() {
+    let config = scratch("whole.toml");
+    std::fs::write(&config, "[currency]\ncents = false\n").unwrap();
+    let config = config.to_str().unwrap();
+    let show = |extra: &[&str]| {
+        let mut args = vec!["show", "examples/overview.bil", "--config", config];
+        args.extend(extra);
+        let out = billig(&args);
+        assert!(out.status.success(), "{}", text(&out.stderr));
+        text(&out.stdout)
+    };
+    assert!(!show(&[]).contains(".00€"));
+    assert!(show(&["--cents"]).contains(".00€"));
+    assert!(!show(&["--cents", "--no-cents"]).contains(".00€"));
+}
+
+#[test]
+fn dpi_out_of_range() {
+    for dpi in ["0", "100000", "4000000000"] {
+        let out = billig(&["plot", "examples/overview.bil", "--plot-target", "png", "--dpi", dpi, "--stdout"]);
+        assert_eq!(out.status.code(), Some(1), "{}", dpi);
+        assert!(text(&out.stderr).contains("between 1 and 600"), "{}", text(&out.stderr));
+        assert!(out.stdout.is_empty());
+    }
+}
+
+#[test]
+fn academic_year_labels() {
+    let out = billig(&["report", "examples/overview.bil", "--year-start", "Sep", "-t", "quarter,year", "--output", "json"]);
+    assert!(out.status.success(), "{}", text(&out.stderr));
+    let tables: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(tables[0]["title"], "Quarterly from Sep");
+    assert_eq!(tables[0]["rows"][1]["start"], "2020-12-01");
+    assert_eq!(tables[1]["title"], "Yearly from Sep");
+    assert_eq!(tables[1]["rows"][0]["end"], "2021-08-31");
+    let out = billig(&["report", "examples/overview.bil", "--week-start", "Sun", "-t", "week", "--output", "json"]);
+    let tables: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(tables[0]["title"], "Weekly from Sun");
+    assert_eq!(tables[0]["rows"][1]["start"], "2020-09-06");
+    let out = billig(&["report", "examples/overview.bil", "--year-start", "Sep", "-t", "year", "--align", "none", "--output", "json"]);
+    let tables: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(tables[0]["title"], "Yearly");
+}
+
+#[test]
+fn add_unquoted_tag() {
+    let file = scratch("tag.bil");
+    let file = file.to_str().unwrap();
+    let add = |words: &[&str]| {
+        let mut args = vec!["add", "-f", file, "-d", "2021-Mar-02"];
+        args.extend(words);
+        billig(&args)
+    };
+    let out = add(&["-12", "Food", "Day", "Lunch", "with", "friends", "at", "Home"]);
+    assert!(out.status.success(), "{}", text(&out.stderr));
+    let contents = std::fs::read_to_string(file).unwrap();
+    assert!(contents.contains("-12, Food, Day, \"Lunch with friends at Home\";"), "{}", contents);
+    let out = add(&["12, Food, Home, Pro, Day, \"x\""]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(text(&out.stderr).matches("Duplicate field definition").count(), 1, "{}", text(&out.stderr));
+}
+
+#[test]
+fn add_keeps_unreadable_file() {
+    let file = scratch("binary.bil");
+    let contents = b"2021:\n    Mar:\n        01: 1, Food, Day, \"\xff\";\n";
+    std::fs::write(&file, contents).unwrap();
+    let out = billig(&["add", "-f", file.to_str().unwrap(), "-d", "2021-Mar-02", "5", "Food", "Day", "x"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(text(&out.stderr).contains("Could not read"), "{}", text(&out.stderr));
+    assert_eq!(std::fs::read(&file).unwrap(), contents);
+}
+
+#[test]
+fn invalid_category_color() {
+    let config = scratch("colors.toml");
+    std::fs::write(&config, "[colors]\nFood = \"dark green\"\n").unwrap();
+    let out = billig(&["plot", "examples/overview.bil", "--stdout", "--config", config.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(text(&out.stderr).contains("'colors.Food'"), "{}", text(&out.stderr));
+    assert!(out.stdout.is_empty());
+}